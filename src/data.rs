@@ -1,15 +1,17 @@
 use std::{collections::HashMap, rc::Rc, cell::RefCell};
 
-use crate::{indic::{IndicatorInput, ComputerMode, SALES_CODE, EBITDA_CODE, EBITA_CODE, CASH_CODE, NET_DEBT_CODE}, fiscalyear::FiscalYear, date::DateKey, compute::ComputeKey};
+use crate::{indic::{IndicatorInput, ComputerMode, SALES_CODE, EBITDA_CODE, EBITA_CODE, CASH_CODE, NET_DEBT_CODE}, fiscalyear::{FiscalYear, FiscalYearError}, date::DateKey, compute::ComputeKey};
 
-use self::{mock::indicator_data, inputs::UserInput};
+use self::{covenant::{Comparison, CovenantRule, Metric, TestFrequency, ValueBasis}, mock::indicator_data, inputs::UserInput, validation::{Sign, ValidationRule}};
 
 pub fn get_all_inputs() -> Vec<IndicatorInput> {
     build_inputs(indicator_data())
 }
 
-pub fn load_context<'y>(context_id: isize) -> Vec<FiscalYear> {
-    mock::fake_years()
+pub fn load_context<'y>(context_id: isize) -> Result<Vec<FiscalYear>, FiscalYearError> {
+    let years = mock::fake_years();
+    FiscalYear::validate(&years)?;
+    Ok(years)
 }
 
 fn build_inputs(data: Vec<IndicatorInputData>) -> Vec<IndicatorInput> {
@@ -23,7 +25,7 @@ fn build_input(input: &IndicatorInputData) -> IndicatorInput {
     IndicatorInput {
         context: input.context,
         code: input.code,
-        input: RefCell::new(UserInput { inputed: input.inputed, computed: input.computed, author: input.author.to_string() }),
+        input: RefCell::new(UserInput::new(input.inputed, input.computed, &input.author)),
         ltm: RefCell::new(Default::default()),
         key: Rc::new(ComputeKey { date: dt, span: input.span })
     }
@@ -39,6 +41,33 @@ pub fn get_config() -> HashMap<&'static isize, ComputerMode> {
     config
 }
 
+pub fn get_rules() -> Vec<CovenantRule> {
+    vec![
+        CovenantRule::build(
+            "Leverage",
+            Metric::ratio(Metric::Indicator(&NET_DEBT_CODE, ValueBasis::Spot), Metric::Indicator(&EBITDA_CODE, ValueBasis::Ltm)),
+            Comparison::LessOrEqual,
+            3.5,
+            TestFrequency::SliceEnd),
+        CovenantRule::build(
+            "Minimum cash",
+            Metric::Indicator(&CASH_CODE, ValueBasis::Spot),
+            Comparison::GreaterOrEqual,
+            1_000_000.0,
+            TestFrequency::Monthly)
+    ]
+}
+
+pub fn get_validations() -> Vec<ValidationRule> {
+    vec![
+        ValidationRule::Sign { code: &SALES_CODE, sign: Sign::Positive },
+        ValidationRule::Sign { code: &CASH_CODE, sign: Sign::Positive },
+        ValidationRule::MaxChange { code: &SALES_CODE, ratio: 1.0 },
+        ValidationRule::NotGreaterThan { code: &EBITA_CODE, other: &EBITDA_CODE },
+        ValidationRule::NotGreaterThan { code: &EBITDA_CODE, other: &SALES_CODE }
+    ]
+}
+
 pub struct IndicatorInputData {
     pub code: &'static isize,
     pub context: isize,
@@ -52,4 +81,8 @@ pub struct IndicatorInputData {
 
 pub mod monitoring;
 pub mod inputs;
-pub mod mock;
\ No newline at end of file
+pub mod mock;
+pub mod covenant;
+pub mod validation;
+#[cfg(test)]
+mod test_support;
\ No newline at end of file
@@ -0,0 +1,286 @@
+use crate::{date::DateKey, indic::{Indicator, IndicatorInput, FY, SLC}, Descriptive};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueBasis {
+    Spot,
+    Period,
+    Ltm
+}
+
+#[derive(Debug, Clone)]
+pub enum Metric {
+    Indicator(&'static isize, ValueBasis),
+    Ratio(Box<Metric>, Box<Metric>)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestFrequency {
+    Monthly,
+    SliceEnd,
+    YearEnd
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleStatus {
+    Pass,
+    Breach,
+    Unavailable
+}
+
+#[derive(Debug, Clone)]
+pub struct CovenantRule {
+    pub name: String,
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    pub frequency: TestFrequency
+}
+
+#[derive(Debug, Clone)]
+pub struct UsedValue {
+    pub label: String,
+    pub code: &'static isize,
+    pub basis: ValueBasis,
+    pub value: Option<f64>
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleTest {
+    pub rule: String,
+    pub date: DateKey,
+    pub status: RuleStatus,
+    pub value: Option<f64>,
+    pub threshold: f64,
+    pub headroom: Option<f64>,
+    pub used_values: Vec<UsedValue>
+}
+
+#[derive(Debug, Default)]
+pub struct CovenantReport {
+    pub tests: Vec<RuleTest>
+}
+
+impl Metric {
+    pub fn ratio(numerator: Metric, denominator: Metric) -> Metric {
+        Metric::Ratio(Box::new(numerator), Box::new(denominator))
+    }
+
+    fn evaluate(&self, inputs: &[IndicatorInput], date: &DateKey, frequency: TestFrequency, used_values: &mut Vec<UsedValue>) -> Option<f64> {
+        match self {
+            Metric::Indicator(code, basis) => {
+                let value = Self::find_value(inputs, code, *basis, date, frequency);
+                used_values.push(UsedValue { label: Self::label(code, *basis), code, basis: *basis, value });
+                value
+            },
+            Metric::Ratio(n, d) => {
+                // both sides are evaluated so that the report lists every value used
+                let numerator = n.evaluate(inputs, date, frequency, used_values);
+                let denominator = d.evaluate(inputs, date, frequency, used_values);
+                match (numerator, denominator) {
+                    (Some(x), Some(y)) if y != 0.0 => Some(x / y),
+                    _ => None
+                }
+            }
+        }
+    }
+
+    fn find_value(inputs: &[IndicatorInput], code: &isize, basis: ValueBasis, date: &DateKey, frequency: TestFrequency) -> Option<f64> {
+        let span = match basis {
+            ValueBasis::Period => frequency.span(),
+            _ => None
+        };
+        let input = inputs.iter().find(|i| i.code == code && i.key.date == *date && i.key.span == span)?;
+        match basis {
+            ValueBasis::Ltm => *input.ltm.borrow(),
+            _ => input.get_value()
+        }
+    }
+
+    fn label(code: &'static isize, basis: ValueBasis) -> String {
+        let name = Indicator::build(Default::default(), code).default_name();
+        match basis {
+            ValueBasis::Spot => name,
+            ValueBasis::Period => format!("Period {}", name),
+            ValueBasis::Ltm => format!("LTM {}", name)
+        }
+    }
+}
+
+impl Comparison {
+    pub fn is_met(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold
+        }
+    }
+
+    pub fn headroom(&self, value: f64, threshold: f64) -> f64 {
+        match self {
+            Comparison::Less | Comparison::LessOrEqual => threshold - value,
+            Comparison::Greater | Comparison::GreaterOrEqual => value - threshold
+        }
+    }
+}
+
+impl TestFrequency {
+    pub fn span(&self) -> Option<&'static str> {
+        match self {
+            TestFrequency::Monthly => None,
+            TestFrequency::SliceEnd => Some(SLC),
+            TestFrequency::YearEnd => Some(FY)
+        }
+    }
+}
+
+impl CovenantRule {
+    pub fn build(name: &str, metric: Metric, comparison: Comparison, threshold: f64, frequency: TestFrequency) -> CovenantRule {
+        CovenantRule { name: name.to_owned(), metric, comparison, threshold, frequency }
+    }
+
+    pub fn test_dates(&self, inputs: &[IndicatorInput]) -> Vec<DateKey> {
+        let span = self.frequency.span();
+        let mut dates: Vec<DateKey> = inputs.iter()
+            .filter(|i| i.key.span == span)
+            .map(|i| i.key.date)
+            .collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    pub fn test(&self, inputs: &[IndicatorInput], date: &DateKey) -> RuleTest {
+        let mut used_values: Vec<UsedValue> = Vec::new();
+        let value = self.metric.evaluate(inputs, date, self.frequency, &mut used_values);
+        let status = match value {
+            None => RuleStatus::Unavailable,
+            Some(x) if self.comparison.is_met(x, self.threshold) => RuleStatus::Pass,
+            _ => RuleStatus::Breach
+        };
+        RuleTest {
+            rule: self.name.clone(),
+            date: *date,
+            status,
+            value,
+            threshold: self.threshold,
+            headroom: value.map(|x| self.comparison.headroom(x, self.threshold)),
+            used_values
+        }
+    }
+
+    pub fn test_all(&self, inputs: &[IndicatorInput]) -> Vec<RuleTest> {
+        self.test_dates(inputs).iter().map(|d| self.test(inputs, d)).collect()
+    }
+}
+
+impl CovenantReport {
+    pub fn build(rules: &[CovenantRule], inputs: &[IndicatorInput]) -> CovenantReport {
+        CovenantReport { tests: rules.iter().flat_map(|r| r.test_all(inputs)).collect() }
+    }
+
+    pub fn breaches(&self) -> Vec<&RuleTest> {
+        self.tests.iter().filter(|t| t.status == RuleStatus::Breach).collect()
+    }
+
+    pub fn is_compliant(&self) -> bool {
+        !self.tests.iter().any(|t| t.status == RuleStatus::Breach)
+    }
+}
+
+impl std::fmt::Display for RuleTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<String> = self.used_values.iter()
+            .map(|v| format!("{}: {}", v.label, v.value.map(|x| x.to_string()).unwrap_or(String::from("None"))))
+            .collect();
+        write!(
+            f,
+            "{} {} {:?} (value {}, threshold {}, headroom {}) [{}]",
+            self.rule,
+            self.date.to_string(),
+            self.status,
+            self.value.map(|x| x.to_string()).unwrap_or(String::from("None")),
+            self.threshold,
+            self.headroom.map(|x| x.to_string()).unwrap_or(String::from("None")),
+            values.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data::test_support::build_input, indic::{CASH_CODE, EBITDA_CODE, NET_DEBT_CODE}};
+    use super::*;
+
+    #[test]
+    fn leverage_breach() {
+        let inputs = vec![
+            build_input(&NET_DEBT_CODE, 3, None, Some(400.0), None),
+            build_input(&EBITDA_CODE, 3, None, Some(10.0), Some(100.0)),
+            build_input(&NET_DEBT_CODE, 3, Some(SLC), None, None),
+            build_input(&NET_DEBT_CODE, 6, None, Some(300.0), None),
+            build_input(&EBITDA_CODE, 6, None, Some(10.0), Some(100.0)),
+            build_input(&NET_DEBT_CODE, 6, Some(SLC), None, None)
+        ];
+        let rule = leverage_rule();
+        let tests = rule.test_all(&inputs);
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].status, RuleStatus::Breach);
+        assert_eq!(tests[0].value, Some(4.0));
+        assert_eq!(tests[0].headroom, Some(-0.5));
+        assert_eq!(tests[0].used_values.len(), 2);
+        assert_eq!(tests[0].used_values[1].label, "LTM EBITDA");
+        assert_eq!(tests[1].status, RuleStatus::Pass);
+        assert_eq!(tests[1].date, DateKey::new(6, 2023));
+    }
+
+    #[test]
+    fn unavailable_values() {
+        let inputs = vec![
+            build_input(&NET_DEBT_CODE, 3, None, Some(400.0), None),
+            build_input(&EBITDA_CODE, 3, None, Some(10.0), Some(0.0)),
+            build_input(&NET_DEBT_CODE, 3, Some(SLC), None, None)
+        ];
+        let tests = leverage_rule().test_all(&inputs);
+        assert_eq!(tests[0].status, RuleStatus::Unavailable);
+        assert_eq!(tests[0].headroom, None);
+    }
+
+    #[test]
+    fn report() {
+        let inputs = vec![
+            build_input(&CASH_CODE, 1, None, Some(2_000_000.0), None),
+            build_input(&CASH_CODE, 2, None, Some(500_000.0), None)
+        ];
+        let rules = vec![CovenantRule::build(
+            "Minimum cash",
+            Metric::Indicator(&CASH_CODE, ValueBasis::Spot),
+            Comparison::GreaterOrEqual,
+            1_000_000.0,
+            TestFrequency::Monthly)];
+        let report = CovenantReport::build(&rules, &inputs);
+        assert_eq!(report.tests.len(), 2);
+        assert!(!report.is_compliant());
+        let breaches = report.breaches();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].date, DateKey::new(2, 2023));
+        assert_eq!(breaches[0].headroom, Some(-500_000.0));
+    }
+
+    fn leverage_rule() -> CovenantRule {
+        CovenantRule::build(
+            "Leverage",
+            Metric::ratio(Metric::Indicator(&NET_DEBT_CODE, ValueBasis::Spot), Metric::Indicator(&EBITDA_CODE, ValueBasis::Ltm)),
+            Comparison::LessOrEqual,
+            3.5,
+            TestFrequency::SliceEnd)
+    }
+}
@@ -1,30 +1,194 @@
 
 use std::collections::HashMap;
 
-use crate::indic::ComputerMode;
+use crate::{compute::ComputeKey, date::datetime::DateTime, indic::{ComputerMode, IndicatorInput}};
 
-use super::get_config;
+use super::{covenant::CovenantRule, get_config, get_rules, get_validations, validation::ValidationRule};
+
+#[derive(Debug, Clone)]
+pub struct InputChange {
+    pub old_value: Option<f64>,
+    pub new_value: Option<f64>,
+    pub author: String,
+    pub timestamp: DateTime,
+    pub reason: String
+}
 
 pub struct UserInput {
-    pub inputed: Option<f64>,
+    // written through change and change_at only, so the history stays complete
+    inputed: Option<f64>,
     pub computed: Option<f64>,
-    pub author: String
+    author: String,
+    history: Vec<InputChange>
+}
+
+impl UserInput {
+    pub fn new(inputed: Option<f64>, computed: Option<f64>, author: &str) -> UserInput {
+        UserInput { inputed, computed, author: author.to_owned(), history: Vec::new() }
+    }
+
+    pub fn inputed(&self) -> Option<f64> {
+        self.inputed
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn history(&self) -> &[InputChange] {
+        &self.history
+    }
+
+    pub fn change(&mut self, value: Option<f64>, author: &str, reason: &str) {
+        // keep timestamps ordered even if the system clock went backwards
+        let now = DateTime::now();
+        let timestamp = match self.history.last() {
+            Some(c) if c.timestamp > now => c.timestamp,
+            _ => now
+        };
+        self.push_change(value, author, reason, timestamp);
+    }
+
+    pub fn change_at(&mut self, value: Option<f64>, author: &str, reason: &str, timestamp: DateTime) -> Result<(), &'static str> {
+        if let Some(c) = self.history.last() {
+            if c.timestamp > timestamp {
+                return Err("Change is older than the last change of the input");
+            }
+        }
+        self.push_change(value, author, reason, timestamp);
+        Ok(())
+    }
+
+    pub fn value_at(&self, at: DateTime) -> Option<f64> {
+        match self.history.iter().rev().find(|c| c.timestamp <= at) {
+            Some(c) => c.new_value,
+            None => match self.history.first() {
+                Some(c) => c.old_value,
+                None => self.inputed
+            }
+        }
+    }
+
+    pub fn revert(&mut self, at: DateTime, author: &str, reason: &str) {
+        let value = self.value_at(at);
+        self.change(value, author, reason);
+    }
+
+    pub fn revert_last(&mut self, author: &str, reason: &str) -> Result<(), &'static str> {
+        let value = match self.history.last() {
+            Some(c) => c.old_value,
+            None => return Err("Input has never been changed")
+        };
+        self.change(value, author, reason);
+        Ok(())
+    }
+
+    fn push_change(&mut self, value: Option<f64>, author: &str, reason: &str, timestamp: DateTime) {
+        self.history.push(InputChange {
+            old_value: self.inputed,
+            new_value: value,
+            author: author.to_owned(),
+            timestamp,
+            reason: reason.to_owned()
+        });
+        self.inputed = value;
+        self.author = author.to_owned();
+    }
+}
+
+pub fn view_as_of(inputs: &[IndicatorInput], key: &ComputeKey, at: DateTime) -> HashMap<&'static isize, Option<f64>> {
+    inputs.iter()
+        .filter(|i| i.key.date == key.date && i.key.span == key.span)
+        .map(|i| (i.code, i.input.borrow().value_at(at)))
+        .collect()
 }
 
 pub struct InputContext {
     pub id: isize,
-    pub configuration: Box<HashMap<&'static isize, ComputerMode>>
+    pub configuration: Box<HashMap<&'static isize, ComputerMode>>,
+    pub rules: Vec<CovenantRule>,
+    pub validations: Vec<ValidationRule>
 }
 
 impl InputContext {
     pub fn build(id: isize) -> InputContext {
         InputContext {
             id,
-            configuration: Box::new(get_config())
+            configuration: Box::new(get_config()),
+            rules: get_rules(),
+            validations: get_validations()
         }
     }
 }
 
+#[cfg(test)]
+mod tests_history {
+    use std::{cell::RefCell, rc::Rc};
+    use crate::{date::DateKey, indic::{CASH_CODE, SALES_CODE}};
+    use super::*;
+
+    #[test]
+    fn append_changes() {
+        let mut input = UserInput::new(Some(1.0), None, "Nobody");
+        input.change_at(Some(2.0), "Alice", "upload", at(10)).unwrap();
+        input.change_at(Some(3.0), "Bob", "correction", at(20)).unwrap();
+        assert_eq!(input.inputed(), Some(3.0));
+        assert_eq!(input.author(), "Bob");
+        assert_eq!(input.history().len(), 2);
+        assert_eq!(input.history()[1].old_value, Some(2.0));
+        assert_eq!(input.history()[1].reason, "correction");
+        assert!(input.change_at(Some(4.0), "Bob", "late", at(15)).is_err());
+    }
+
+    #[test]
+    fn value_as_of() {
+        let mut input = UserInput::new(Some(1.0), None, "Nobody");
+        assert_eq!(input.value_at(at(0)), Some(1.0));
+        input.change_at(Some(2.0), "Alice", "upload", at(10)).unwrap();
+        input.change_at(None, "Bob", "removed", at(20)).unwrap();
+        assert_eq!(input.value_at(at(5)), Some(1.0));
+        assert_eq!(input.value_at(at(10)), Some(2.0));
+        assert_eq!(input.value_at(at(15)), Some(2.0));
+        assert_eq!(input.value_at(at(25)), None);
+    }
+
+    #[test]
+    fn revert_input() {
+        let mut input = UserInput::new(None, None, "Nobody");
+        assert!(input.revert_last("Alice", "nothing to revert").is_err());
+        input.change_at(Some(2.0), "Alice", "upload", at(10)).unwrap();
+        input.change_at(Some(5.0), "Bob", "upload", at(20)).unwrap();
+        input.revert_last("Carol", "wrong upload").unwrap();
+        assert_eq!(input.inputed(), Some(2.0));
+        input.revert(at(0), "Carol", "back to initial");
+        assert_eq!(input.inputed(), None);
+        assert_eq!(input.history().len(), 4);
+        assert_eq!(input.history()[3].author, "Carol");
+    }
+
+    #[test]
+    fn view_key() {
+        let key = ComputeKey { date: DateKey::new(1, 2023), span: None };
+        let inputs: Vec<IndicatorInput> = [&SALES_CODE, &CASH_CODE].into_iter().map(|code| IndicatorInput {
+            code,
+            input: RefCell::new(UserInput::new(Some(1.0), None, "Nobody")),
+            ltm: RefCell::new(None),
+            context: 1,
+            key: Rc::new(ComputeKey { date: key.date, span: None })
+        }).collect();
+        inputs[0].input.borrow_mut().change_at(Some(8.0), "Alice", "upload", at(10)).unwrap();
+        let view = view_as_of(&inputs, &key, at(5));
+        assert_eq!(view.get(&SALES_CODE), Some(&Some(1.0)));
+        let view = view_as_of(&inputs, &key, at(10));
+        assert_eq!(view.get(&SALES_CODE), Some(&Some(8.0)));
+        assert_eq!(view.get(&CASH_CODE), Some(&Some(1.0)));
+    }
+
+    fn at(seconds: i64) -> DateTime {
+        DateTime::from_timestamp(seconds, 0)
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use crate::{data::{mock, build_inputs, get_config}, indic::{SALES_CODE, CASH_CODE}};
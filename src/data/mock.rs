@@ -1,8 +1,7 @@
 use std::ops::{Range, RangeInclusive};
-use std::rc::Rc;
 
 use crate::date::DateKey;
-use crate::fiscalyear::FiscalYear;
+use crate::fiscalyear::{FiscalYear, calendar::FiscalCalendar};
 use crate::indic::{SALES_CODE, CASH_CODE, SLC, NET_DEBT_CODE, FY, EBITDA_CODE, EBITA_CODE};
 use crate::data::IndicatorInputData;
 
@@ -13,29 +12,8 @@ pub fn fake_context(default_month: u8, all_years: Vec<i32>, initial_month: Optio
 pub fn fake_years<'y>() -> Vec<FiscalYear> {
     const START: u8 = 3;
     const DELAY: u8 = 8;
-    let mut year = 2019;
-    let mut years: Vec<FiscalYear> = vec![];
-    
-    let mut rg = START..=DELAY;
-    let y: Vec<DateKey> = rg.into_iter().map(|x| DateKey::new(x, year)).collect();
-    years.push(FiscalYear::build(Rc::new(y)));
-    let delay: i8 = DELAY as i8 - 12;
-    for _ in 0..2 {
-        rg = 1..=12;
-        let y: Vec<DateKey> = rg.into_iter().map(|x| {
-            let mut m: i8 = (x as i8) + delay;
-            if m <= 0 {
-                m += 12;
-            }
-            else if m == 1 {
-                year += 1;
-            }
-            DateKey::new( m as u8, year)
-        }).collect();
-        years.push(FiscalYear::build(Rc::new(y)));
-    }
-
-    years
+    let calendar = FiscalCalendar::build(DateKey::new(START, 2019), DELAY, DateKey::new(DELAY, 2021));
+    calendar.unwrap().generate()
 }
 
 pub fn indicator_data() -> Vec<IndicatorInputData> {
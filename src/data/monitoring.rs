@@ -1,9 +1,9 @@
 
 use std::cell::RefCell;
 
-use crate::{fiscalyear::FiscalYear, indic::{IndicatorInput, SLC, FY, LTM, ComputerMode}, date::DateKey};
+use crate::{fiscalyear::FiscalYear, indic::{IndicatorInput, SLC, FY, LTM, ComputerMode, Weighting}, date::{range::DateKeyRange, DateKey}};
 use crate::compute::{ComputeError, ComputeKey};
-use super::inputs::InputContext;
+use super::{covenant::CovenantReport, inputs::InputContext, validation::{self, ValidationWarning}};
 
 
 pub struct InputMonitoring {
@@ -44,6 +44,14 @@ impl InputMonitoring {
         Ok(inputs)
     }
 
+    pub fn validate(&self, inputs: &[IndicatorInput]) -> Vec<ValidationWarning> {
+        validation::validate(&self.context.validations, inputs)
+    }
+
+    pub fn check_covenants(&self, inputs: &[IndicatorInput]) -> CovenantReport {
+        CovenantReport::build(&self.context.rules, inputs)
+    }
+
     pub fn compute_by_key(&self, inputs: &mut Vec<IndicatorInput>, key: &ComputeKey) -> Result<(), &'static str> {
         if inputs.len() == 0 {
             return Err("Indicator inputs list is empty");
@@ -86,6 +94,8 @@ impl InputMonitoring {
 
     fn compute_slice(&self, inputs: &mut Vec<IndicatorInput>, slice: &Vec<DateKey>, key: &ComputeKey, span: Option<&str>, children_spans: &Vec<Option<&str>>) -> Result<(), &'static str> {
         let mut slice_inputs = self.extract_inputs(inputs, slice, span, children_spans);
+        // weights are looked up among all the inputs of the slice
+        let weight_inputs = slice_inputs.clone();
         // emulate group by
         let mut codes:Vec<&'static isize> = slice_inputs.iter().map(|i| i.code).collect();
         while codes.len() > 0 {
@@ -97,11 +107,11 @@ impl InputMonitoring {
                 None => break
             }
             if span == Some(&LTM) {
-                if let Some(value) = self.compute_ltm_of_indicator(&slice_inputs, slice, &key.date, code) {
+                if let Some(value) = self.compute_ltm_of_indicator(&slice_inputs, &weight_inputs, slice, &key.date, code) {
                     return value;
                 }
             }
-            else if let Some(value) = self.compute_slice_of_indicator(&slice_inputs, code, span, children_spans) {
+            else if let Some(value) = self.compute_slice_of_indicator(&slice_inputs, &weight_inputs, code, span, children_spans) {
                 return value;
             }
             // keep other inputs
@@ -146,11 +156,12 @@ impl InputMonitoring {
 
     fn compute_slice_of_indicator(
         &self,
-        slice_inputs: &Vec<&mut IndicatorInput>, 
+        slice_inputs: &Vec<&IndicatorInput>, 
+        weight_inputs: &Vec<&IndicatorInput>, 
         code: &'static isize, 
         target_span: Option<&str>, 
         item_spans: &Vec<Option<&str>>) -> Option<Result<(), &'static str>> {
-        let indic_inputs: Vec<&&mut IndicatorInput> = slice_inputs.iter()
+        let indic_inputs: Vec<&&IndicatorInput> = slice_inputs.iter()
             .filter(|i| i.code == code)
             .collect();
         if indic_inputs.len() == 0 {
@@ -167,9 +178,15 @@ impl InputMonitoring {
             },
             _ => {}
         }
-        let input_values = self.extract_values(&indic_inputs, item_spans[0]);
+        let result = match computer {
+            ComputerMode::WeightedAvg(w) => {
+                let (input_values, weights) = self.extract_weighted_values(weight_inputs, &indic_inputs, item_spans[0], w);
+                computer.compute_weighted(&input_values, &weights)
+            },
+            _ => computer.compute(&self.extract_values(&indic_inputs, item_spans[0]))
+        };
         
-        match computer.compute(&input_values) {
+        match result {
             Ok(x) => {
                 target_input.map(|val| val.input.borrow_mut().computed = Some(x));
             },
@@ -186,8 +203,8 @@ impl InputMonitoring {
         None
     }
 
-    fn compute_ltm_of_indicator(&self, slice_inputs: &Vec<&mut IndicatorInput>, slice: &Vec<DateKey>, date: &DateKey, code: &'static isize) -> Option<Result<(), &'static str>> {
-        let indic_inputs: Vec<&&mut IndicatorInput> = slice_inputs.iter()
+    fn compute_ltm_of_indicator(&self, slice_inputs: &Vec<&IndicatorInput>, weight_inputs: &Vec<&IndicatorInput>, slice: &Vec<DateKey>, date: &DateKey, code: &'static isize) -> Option<Result<(), &'static str>> {
+        let indic_inputs: Vec<&&IndicatorInput> = slice_inputs.iter()
             .filter(|i| i.code == code)
             .collect();
         if indic_inputs.len() == 0 {
@@ -203,13 +220,23 @@ impl InputMonitoring {
             _ => {}
         }
         let mode = self.context.configuration.get(code).expect("Unable to determine compute mode");
-        let input_values = match mode {
-            ComputerMode::AddUp | ComputerMode::Avg => self.extract_ltm_combinable_values(date, slice, &indic_inputs, mode),
-            _ => self.extract_ltm_values(date, &indic_inputs)
+        let computer = indic_inputs.first().unwrap().get_computer(&self.context.configuration);
+        let result = match mode {
+            ComputerMode::AddUp | ComputerMode::Avg => computer.compute(&self.extract_ltm_combinable_values(date, slice, &indic_inputs, mode)),
+            ComputerMode::WeightedAvg(w) => match self.is_ltm_available(date, slice, &indic_inputs, mode) {
+                Ok(true) => {
+                    let (input_values, weights) = self.extract_ltm_weighted_values(date, weight_inputs, &indic_inputs, w);
+                    computer.compute_weighted(&input_values, &weights)
+                },
+                Ok(false) => Err(ComputeError::new(String::new())),
+                Err(e) => return Some(Err(e))
+            },
+            ComputerMode::Default | ComputerMode::First | ComputerMode::Min | ComputerMode::Max | ComputerMode::Median | ComputerMode::Complex(_) => {
+                computer.compute(&self.extract_ltm_values(date, &indic_inputs))
+            }
         };
         
-        let computer = indic_inputs.first().unwrap().get_computer(&self.context.configuration);
-        match computer.compute(&input_values) {
+        match result {
             Ok(x) => {
                 target_input.map(|val| *val.ltm.borrow_mut() = Some(x));
             },
@@ -226,8 +253,8 @@ impl InputMonitoring {
         None
     }
     
-    fn extract_inputs<'a>(&self, inputs: &'a mut Vec<IndicatorInput>, slice: &Vec<DateKey>, parent_span: Option<&str>, children_spans: &Vec<Option<&str>>) -> Vec<&'a mut IndicatorInput> {
-        let slice_inputs: Vec<_> = inputs.iter_mut()
+    fn extract_inputs<'a>(&self, inputs: &'a [IndicatorInput], slice: &Vec<DateKey>, parent_span: Option<&str>, children_spans: &Vec<Option<&str>>) -> Vec<&'a IndicatorInput> {
+        let slice_inputs: Vec<_> = inputs.iter()
             .filter(|i| i.key.span == parent_span || children_spans.iter().any(|s| i.key.span == *s))
             .filter(|i| slice.iter().any(|d| i.key.date == *d))
             .collect();
@@ -235,13 +262,18 @@ impl InputMonitoring {
         slice_inputs
     }
 
-    fn extract_values(&self, indic_inputs: &Vec<&&mut IndicatorInput>, span: Option<&str>) -> Vec<Box<f64>> {
+    fn extract_values(&self, indic_inputs: &Vec<&&IndicatorInput>, span: Option<&str>) -> Vec<Box<f64>> {
         let mut input_values: Vec<Box<f64>> = Vec::new();
-        indic_inputs
+        let mut span_inputs: Vec<_> = indic_inputs
             .iter()
             .filter(|&&i| i.key.span == span)
+            .collect();
+        // first and last values depend on the order of dates
+        span_inputs.sort_by_key(|i| i.key.date);
+        span_inputs
+            .iter()
             .for_each(|&i| {
-                let mut o = i.input.borrow().inputed;
+                let mut o = i.input.borrow().inputed();
                 if o == None {
                     o = i.input.borrow().computed;
                 }
@@ -253,19 +285,70 @@ impl InputMonitoring {
         input_values
     }
 
-    fn extract_ltm_values(&self, end_date: &DateKey, indic_inputs: &Vec<&&mut IndicatorInput>) -> Vec<Box<f64>> {
-        let mut start_date = *end_date;
-        start_date.add_months(-12);
+    fn extract_weighted_values(&self, weight_inputs: &Vec<&IndicatorInput>, indic_inputs: &Vec<&&IndicatorInput>, span: Option<&str>, weighting: &Weighting) -> (Vec<Box<f64>>, Vec<f64>) {
+        let mut input_values: Vec<Box<f64>> = Vec::new();
+        let mut weights: Vec<f64> = Vec::new();
+        indic_inputs
+            .iter()
+            .filter(|&&i| i.key.span == span)
+            .for_each(|&i| {
+                if let (Some(f), Some(w)) = (i.get_value(), self.get_weight(weight_inputs, i, weighting)) {
+                    input_values.push(Box::new(f));
+                    weights.push(w);
+                }
+            });
+        (input_values, weights)
+    }
+
+    fn get_weight(&self, weight_inputs: &Vec<&IndicatorInput>, input: &IndicatorInput, weighting: &Weighting) -> Option<f64> {
+        match weighting {
+            Weighting::DaysInMonth => self.count_days(&input.key),
+            Weighting::Indicator(code) => weight_inputs.iter()
+                .find(|i| i.code == *code && i.key.span == input.key.span && i.key.date == input.key.date)
+                .and_then(|i| i.get_value())
+        }
+    }
+
+    fn count_days(&self, key: &ComputeKey) -> Option<f64> {
+        let months = match key.span {
+            None => vec![key.date],
+            Some(SLC) => FiscalYear::find(&self.years, &key.date).ok()?.find_slice(&key.date).ok()?,
+            Some(FY) => FiscalYear::find(&self.years, &key.date).ok()?.get_months(),
+            _ => return None
+        };
+        Some(months.iter().map(|m| m.nb_days() as f64).sum())
+    }
+
+    fn extract_ltm_month_inputs<'b>(&self, end_date: &DateKey, indic_inputs: &'b Vec<&&IndicatorInput>) -> Vec<&'b &'b IndicatorInput> {
+        let ltm = DateKeyRange::ltm(end_date);
         let mut month_inputs: Vec<_> = indic_inputs.iter()
-            .filter(|i| start_date <= i.key.date && i.key.date <= *end_date)
+            .filter(|i| ltm.contains(&i.key.date))
             .filter(|i| i.key.span == None)
+            .map(|i| *i)
             .collect();
 
-        month_inputs.sort_by(|&&a, &&b| a.key.date.cmp(&b.key.date));
+        month_inputs.sort_by_key(|i| i.key.date);
+        month_inputs
+    }
+
+    fn extract_ltm_weighted_values(&self, end_date: &DateKey, weight_inputs: &Vec<&IndicatorInput>, indic_inputs: &Vec<&&IndicatorInput>, weighting: &Weighting) -> (Vec<Box<f64>>, Vec<f64>) {
+        let mut input_values: Vec<Box<f64>> = Vec::new();
+        let mut weights: Vec<f64> = Vec::new();
+        self.extract_ltm_month_inputs(end_date, indic_inputs).iter().for_each(|&i| {
+            if let (Some(f), Some(w)) = (i.get_value(), self.get_weight(weight_inputs, i, weighting)) {
+                input_values.push(Box::new(f));
+                weights.push(w);
+            }
+        });
+        (input_values, weights)
+    }
+
+    fn extract_ltm_values(&self, end_date: &DateKey, indic_inputs: &Vec<&&IndicatorInput>) -> Vec<Box<f64>> {
+        let month_inputs = self.extract_ltm_month_inputs(end_date, indic_inputs);
         
         let mut input_values: Vec<Box<f64>> = Vec::new();
         month_inputs.iter().for_each(|i| {
-            let mut o = i.input.borrow().inputed;
+            let mut o = i.input.borrow().inputed();
             if o == None {
                 o = i.input.borrow().computed;
             }
@@ -277,7 +360,14 @@ impl InputMonitoring {
         input_values
     }
 
-    fn extract_ltm_combinable_values(&self, end_date: &DateKey, slice: &Vec<DateKey>, indic_inputs: &Vec<&&mut IndicatorInput>, mode: &ComputerMode) -> Vec<Box<f64>> {
+    // same rule as the combinable values: enough slices of the LTM must be known
+    fn is_ltm_available(&self, end_date: &DateKey, slice: &Vec<DateKey>, indic_inputs: &Vec<&&IndicatorInput>, mode: &ComputerMode) -> Result<bool, &'static str> {
+        let x: Vec<&IndicatorInput> = indic_inputs.iter().map(|i| &***i).collect();
+        let mut ltm = LtmSumHandler::new(end_date, slice, &x, mode);
+        Ok(!ltm.verify()?.ltm_data.is_unavailable)
+    }
+
+    fn extract_ltm_combinable_values(&self, end_date: &DateKey, slice: &Vec<DateKey>, indic_inputs: &Vec<&&IndicatorInput>, mode: &ComputerMode) -> Vec<Box<f64>> {
         let x: Vec<&IndicatorInput> = indic_inputs.iter().map(|i| &***i).collect();
         let mut ltm = LtmSumHandler::new(end_date, slice, &x, mode);
         let ltm = ltm.verify();
@@ -295,8 +385,7 @@ impl InputMonitoring {
 
 #[derive(Clone)]
 struct LtmInputs<'a> {
-    start_date: DateKey,
-    end_date: DateKey,
+    range: DateKeyRange,
     dates: Vec<DateKey>,
     month_inputs: Vec<&'a IndicatorInput>,
     slice_inputs: Vec<&'a IndicatorInput>,
@@ -366,7 +455,7 @@ impl<'a> LtmSumHandler<'a> {
                 self.ltm_data.buffer.borrow_mut().push(v);
             }
         }
-        if *current_date < self.ltm_data.start_date || *current_date > self.ltm_data.end_date {
+        if !self.ltm_data.range.contains(current_date) {
             self.ltm_data.bypass_buffer.borrow_mut().push(self.ltm_data.buffer.borrow_mut().pop().unwrap());
         }
     }
@@ -378,7 +467,7 @@ impl<'a> LtmSumHandler<'a> {
         if let Some(v) = s {
             let nb_bypass = self.ltm_data.bypass_buffer.borrow().len();
             let nb_buffered = self.ltm_data.buffer.borrow().len();
-            if let Some(x) = v.input.borrow().inputed {
+            if let Some(x) = v.input.borrow().inputed() {
                 if nb_bypass == 0 {
                     self.ltm_data.values.borrow_mut().push(Box::new(x));
                 }
@@ -411,7 +500,7 @@ impl<'a> LtmInit<'a> {
             return Err("Missing slice inputs");
         }
 
-        if self.ltm_data.slice_inputs.iter().filter(|i| i.input.borrow().computed != None || i.input.borrow().inputed != None).count() < self.ltm_data.min_slc {
+        if self.ltm_data.slice_inputs.iter().filter(|i| i.input.borrow().computed != None || i.input.borrow().inputed() != None).count() < self.ltm_data.min_slc {
             self.ltm_data.is_unavailable = true;
         }
 
@@ -421,11 +510,8 @@ impl<'a> LtmInit<'a> {
 
 impl<'a> LtmInputs<'a> {
     fn build(date: &'a DateKey, slice: &'a Vec<DateKey>, inputs: &'a Vec<&'a IndicatorInput>, mode: &'a ComputerMode) -> LtmInputs<'a> {
-        let mut start_date = *date;
-        start_date.add_months(-12);
         let ltm = LtmInputs { 
-            start_date, 
-            end_date: *date, 
+            range: DateKeyRange::ltm(date), 
             dates: slice.into_iter().map(|d| *d).collect(), 
             month_inputs: inputs.iter().filter(|i| i.key.span == None).map(|i| *i).collect(),
             slice_inputs: inputs.iter().filter(|i| i.key.span == Some(&SLC)).map(|i| *i).collect(),
@@ -443,7 +529,7 @@ impl<'a> LtmInputs<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{date::DateKey, indic::{ComputerMode, SALES_CODE}, data::inputs::UserInput};
+    use crate::{date::DateKey, indic::{ComputerMode, Weighting, SALES_CODE, EBITDA_CODE, CASH_CODE}, data::{inputs::UserInput, test_support::build_input}};
     use std::{cell::RefCell, rc::Rc, collections::HashMap};
     use super::*;
 
@@ -497,6 +583,79 @@ mod tests {
         assert!(!ltm.unwrap().ltm_data.is_unavailable);
     }
 
+    #[test]
+    fn weighted_slice_by_days() {
+        let mut config = HashMap::new();
+        config.insert(&SALES_CODE, ComputerMode::WeightedAvg(Weighting::DaysInMonth));
+        let monitor = build_monitor(config);
+        let mut inputs = vec![
+            build_input(&SALES_CODE, 1, None, Some(31.0), None),
+            build_input(&SALES_CODE, 2, None, Some(28.0), None),
+            build_input(&SALES_CODE, 3, None, Some(31.0), None),
+            build_input(&SALES_CODE, 3, Some(&SLC), None, None)
+        ];
+        let key = ComputeKey { date: DateKey::new(3, 2023), span: Some(&SLC) };
+        monitor.compute_by_key(&mut inputs, &key).unwrap();
+        let expected = (31.0 * 31.0 + 28.0 * 28.0 + 31.0 * 31.0) / 90.0;
+        assert!((inputs[3].get_value().unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weighted_slice_by_indicator() {
+        let mut config = HashMap::new();
+        config.insert(&SALES_CODE, ComputerMode::AddUp);
+        config.insert(&EBITDA_CODE, ComputerMode::WeightedAvg(Weighting::Indicator(&SALES_CODE)));
+        let monitor = build_monitor(config);
+        let mut inputs = vec![
+            build_input(&SALES_CODE, 1, None, Some(100.0), None),
+            build_input(&SALES_CODE, 2, None, Some(300.0), None),
+            build_input(&SALES_CODE, 3, None, None, None),
+            build_input(&SALES_CODE, 3, Some(&SLC), None, None),
+            build_input(&EBITDA_CODE, 1, None, Some(0.1), None),
+            build_input(&EBITDA_CODE, 2, None, Some(0.2), None),
+            build_input(&EBITDA_CODE, 3, None, Some(0.9), None),
+            build_input(&EBITDA_CODE, 3, Some(&SLC), None, None)
+        ];
+        let key = ComputeKey { date: DateKey::new(3, 2023), span: Some(&SLC) };
+        monitor.compute_by_key(&mut inputs, &key).unwrap();
+        assert!((inputs[7].get_value().unwrap() - 0.175).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ltm_max() {
+        let mut config = HashMap::new();
+        config.insert(&CASH_CODE, ComputerMode::Max);
+        let monitor = build_monitor(config);
+        let mut inputs: Vec<IndicatorInput> = (1..=12).map(|m| build_input(&CASH_CODE, m, None, Some(m as f64 * if m == 4 { 10.0 } else { 1.0 }), None)).collect();
+        let key = ComputeKey { date: DateKey::new(12, 2023), span: Some(&LTM) };
+        monitor.compute_by_key(&mut inputs, &key).unwrap();
+        assert_eq!(*inputs[11].ltm.borrow(), Some(40.0));
+    }
+
+    #[test]
+    fn weighted_ltm_with_missing_months() {
+        let mut config = HashMap::new();
+        config.insert(&SALES_CODE, ComputerMode::WeightedAvg(Weighting::DaysInMonth));
+        let monitor = build_monitor(config);
+        let key = ComputeKey { date: DateKey::new(12, 2023), span: Some(&LTM) };
+        // months of the first slices are missing, so are their values
+        let mut inputs: Vec<IndicatorInput> = (7..=12).map(|m| build_input(&SALES_CODE, m, None, Some(10.0), None)).collect();
+        inputs.extend([3, 6, 9, 12].map(|m| build_input(&SALES_CODE, m, Some(&SLC), if m > 6 { Some(10.0) } else { None }, None)));
+        monitor.compute_by_key(&mut inputs, &key).unwrap();
+        assert_eq!(*inputs[5].ltm.borrow(), None);
+
+        let mut inputs: Vec<IndicatorInput> = (1..=12).map(|m| build_input(&SALES_CODE, m, None, Some(10.0), None)).collect();
+        inputs.extend([3, 6, 9, 12].map(|m| build_input(&SALES_CODE, m, Some(&SLC), Some(10.0), None)));
+        monitor.compute_by_key(&mut inputs, &key).unwrap();
+        assert!((inputs[11].ltm.borrow().unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    fn build_monitor(config: HashMap<&'static isize, ComputerMode>) -> InputMonitoring {
+        let months: Vec<DateKey> = (1..=12).map(|m| DateKey::new(m, 2023)).collect();
+        let context = InputContext { id: 1, configuration: Box::new(config), rules: vec![], validations: vec![] };
+        InputMonitoring::build(context, vec![FiscalYear::build(Rc::new(months))])
+    }
+
     fn init_ltm_data<'a>() -> (DateKey, Vec<DateKey>, ComputerMode) {
         let date = DateKey::new(1, 2023);
         let mut slice: Vec<DateKey> = (1..7).into_iter().map(|x| DateKey::new(x, 2023)).collect();
@@ -514,7 +673,7 @@ mod tests {
             };
             IndicatorInput { 
                 code: &SALES_CODE, 
-                input: RefCell::new(UserInput { author: String::new(), computed: None, inputed: value }), 
+                input: RefCell::new(UserInput::new(value, None, "")), 
                 ltm: RefCell::new(None), 
                 context: 1, 
                 key: Rc::new(ComputeKey { date: DateKey::new(m, y), span: Some(&SLC) })
@@ -0,0 +1,16 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{compute::ComputeKey, date::DateKey, indic::IndicatorInput};
+
+use super::inputs::UserInput;
+
+// input of the month m of 2023, span None for a month
+pub fn build_input(code: &'static isize, m: u8, span: Option<&'static str>, value: Option<f64>, ltm: Option<f64>) -> IndicatorInput {
+    IndicatorInput {
+        code,
+        input: RefCell::new(UserInput::new(value, None, "Someone")),
+        ltm: RefCell::new(ltm),
+        context: 1,
+        key: Rc::new(ComputeKey { date: DateKey::new(m, 2023), span })
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{date::DateKey, indic::{Indicator, IndicatorInput}, Descriptive};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sign {
+    Positive,
+    Negative
+}
+
+#[derive(Debug, Clone)]
+pub enum ValidationRule {
+    Sign { code: &'static isize, sign: Sign },
+    Range { code: &'static isize, min: Option<f64>, max: Option<f64> },
+    MaxChange { code: &'static isize, ratio: f64 },
+    NotGreaterThan { code: &'static isize, other: &'static isize }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationWarning {
+    pub code: &'static isize,
+    pub date: DateKey,
+    pub span: Option<&'static str>,
+    pub value: f64,
+    pub author: String,
+    pub message: String
+}
+
+impl ValidationRule {
+    pub fn get_code(&self) -> &'static isize {
+        match self {
+            ValidationRule::Sign { code, .. }
+            | ValidationRule::Range { code, .. }
+            | ValidationRule::MaxChange { code, .. }
+            | ValidationRule::NotGreaterThan { code, .. } => code
+        }
+    }
+
+    pub fn check(&self, inputs: &[IndicatorInput]) -> Vec<ValidationWarning> {
+        inputs.iter()
+            .filter(|i| i.code == self.get_code())
+            .filter_map(|i| {
+                let value = i.input.borrow().inputed()?;
+                let message = self.check_value(inputs, i, value)?;
+                Some(ValidationWarning {
+                    code: i.code,
+                    date: i.key.date,
+                    span: i.key.span,
+                    value,
+                    author: i.input.borrow().author().to_owned(),
+                    message
+                })
+            })
+            .collect()
+    }
+
+    fn check_value(&self, inputs: &[IndicatorInput], input: &IndicatorInput, value: f64) -> Option<String> {
+        let name = Self::name(input.code);
+        match self {
+            ValidationRule::Sign { sign: Sign::Positive, .. } if value < 0.0 => {
+                Some(format!("{} should not be negative", name))
+            },
+            ValidationRule::Sign { sign: Sign::Negative, .. } if value > 0.0 => {
+                Some(format!("{} should not be positive", name))
+            },
+            ValidationRule::Range { min: Some(min), .. } if value < *min => {
+                Some(format!("{} is lower than {}", name, min))
+            },
+            ValidationRule::Range { max: Some(max), .. } if value > *max => {
+                Some(format!("{} is greater than {}", name, max))
+            },
+            ValidationRule::MaxChange { ratio, .. } => {
+                if input.key.span.is_some() {
+                    return None;
+                }
+                let mut previous_date = input.key.date;
+                previous_date.add_months(-1);
+                let previous = Self::find_inputed(inputs, input.code, &previous_date, None)?;
+                if previous == 0.0 {
+                    return None;
+                }
+                let change = ((value - previous) / previous).abs();
+                if change > *ratio {
+                    return Some(format!(
+                        "{} changed by {:.1}% since {} (maximum {:.1}%)",
+                        name, change * 100.0, previous_date.to_string(), ratio * 100.0));
+                }
+                None
+            },
+            ValidationRule::NotGreaterThan { other, .. } => {
+                let other_value = Self::find_inputed(inputs, other, &input.key.date, input.key.span)?;
+                if value > other_value {
+                    return Some(format!("{} should not be greater than {} ({})", name, Self::name(other), other_value));
+                }
+                None
+            },
+            _ => None
+        }
+    }
+
+    fn find_inputed(inputs: &[IndicatorInput], code: &isize, date: &DateKey, span: Option<&str>) -> Option<f64> {
+        inputs.iter()
+            .find(|i| i.code == code && i.key.date == *date && i.key.span == span)
+            .and_then(|i| i.input.borrow().inputed())
+    }
+
+    fn name(code: &'static isize) -> String {
+        Indicator::build(Default::default(), code).default_name()
+    }
+}
+
+pub fn validate(rules: &[ValidationRule], inputs: &[IndicatorInput]) -> Vec<ValidationWarning> {
+    rules.iter().flat_map(|r| r.check(inputs)).collect()
+}
+
+impl std::fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}: {} (value {}, entered by {})",
+            self.date.to_string(),
+            self.span.unwrap_or("month"),
+            Indicator::build(Default::default(), self.code).default_name(),
+            self.message,
+            self.value,
+            self.author
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data::test_support::build_input, indic::{EBITA_CODE, EBITDA_CODE, SALES_CODE, SLC}};
+    use super::*;
+
+    #[test]
+    fn sign_and_range() {
+        let inputs = vec![
+            build_input(&SALES_CODE, 1, None, Some(-5.0), None),
+            build_input(&SALES_CODE, 2, None, Some(50.0), None),
+            build_input(&SALES_CODE, 3, None, Some(5000.0), None),
+            build_input(&SALES_CODE, 3, Some(SLC), None, None)
+        ];
+        let rules = vec![
+            ValidationRule::Sign { code: &SALES_CODE, sign: Sign::Positive },
+            ValidationRule::Range { code: &SALES_CODE, min: None, max: Some(1000.0) }
+        ];
+        let warnings = validate(&rules, &inputs);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].date, DateKey::new(1, 2023));
+        assert_eq!(warnings[0].author, "Someone");
+        assert_eq!(warnings[1].value, 5000.0);
+    }
+
+    #[test]
+    fn max_change() {
+        let inputs = vec![
+            build_input(&SALES_CODE, 1, None, Some(100.0), None),
+            build_input(&SALES_CODE, 2, None, Some(120.0), None),
+            build_input(&SALES_CODE, 3, None, Some(300.0), None)
+        ];
+        let warnings = ValidationRule::MaxChange { code: &SALES_CODE, ratio: 0.5 }.check(&inputs);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].date, DateKey::new(3, 2023));
+    }
+
+    #[test]
+    fn cross_indicators() {
+        let inputs = vec![
+            build_input(&EBITDA_CODE, 1, None, Some(10.0), None),
+            build_input(&EBITA_CODE, 1, None, Some(8.0), None),
+            build_input(&EBITDA_CODE, 2, None, Some(10.0), None),
+            build_input(&EBITA_CODE, 2, None, Some(12.0), None),
+            build_input(&EBITA_CODE, 3, None, Some(12.0), None)
+        ];
+        let warnings = ValidationRule::NotGreaterThan { code: &EBITA_CODE, other: &EBITDA_CODE }.check(&inputs);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].date, DateKey::new(2, 2023));
+    }
+}
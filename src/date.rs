@@ -81,6 +81,18 @@ impl DateKey {
         Ok(())
     }
 
+    pub fn nb_days(&self) -> u8 {
+        max_day_of_month(self.month, self.year)
+    }
+
+    pub fn first_day(&self) -> DayDate {
+        DayDate { num: 1, date_key: *self }
+    }
+
+    pub fn last_day(&self) -> DayDate {
+        DayDate { num: self.nb_days(), date_key: *self }
+    }
+
     pub fn add_months(&mut self, n: i32) {
         if n == 0 {
             return;
@@ -159,6 +171,53 @@ impl Ord for DateKey {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday,
+        Weekday::Friday, Weekday::Saturday, Weekday::Sunday
+    ];
+
+    // ISO number, from 1 (monday) to 7 (sunday)
+    pub fn from_iso(n: u8) -> Result<Weekday, DateError> {
+        if !(1..=7).contains(&n) {
+            return Err(DateError { details: String::from("Weekday has to be between 1 and 7") });
+        }
+        Ok(Self::ALL[n as usize - 1])
+    }
+
+    // english name or its first three letters, case insensitive
+    pub fn from_name(name: &str) -> Result<Weekday, DateError> {
+        let lower = name.trim().to_lowercase();
+        let found = Self::ALL.iter().find(|w| {
+            let full = format!("{:?}", w).to_lowercase();
+            lower == full || (lower.len() == 3 && full.starts_with(&lower))
+        });
+        match found {
+            Some(w) => Ok(*w),
+            None => Err(DateError { details: format!("Unknown weekday '{}'", name) })
+        }
+    }
+
+    pub fn iso_number(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    pub fn is_weekend(&self) -> bool {
+        matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Default)]
 pub struct DayDate {
     num: u8,
@@ -263,64 +322,78 @@ impl DayDate {
 
     pub fn add_days(&mut self, n: i32) {
         if n == 0 { return }
-        if n < 0 {
-            self.subtract_days(n);
-        }
-        else {
-            self.append_days(n);
-        }
+        *self = DayDate::from_day_number(self.day_number() + n as i64);
     }
 
+    // the day is capped to the end of the new month
     pub fn add_months(&mut self, n: i32) {
         self.date_key.add_months(n);
+        self.num = self.num.min(self.date_key.nb_days());
     }
 
     pub fn add_years(&mut self, n: i32) {
         self.add_months(n * MAX_MONTH  as i32);
     }
 
-    fn subtract_days(&mut self, n: i32) {
-        assert!(n < 0);
-        if n + self.num as i32 > 0 {
-            self.num += n as u8;
-            return;
-        }
+    // number of days since 1970-01-01
+    pub(crate) fn day_number(&self) -> i64 {
+        let m = self.month() as i64;
+        let y = self.year() as i64 - if m <= 2 { 1 } else { 0 };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * ((m + 9) % 12) + 2) / 5 + self.day() as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
 
-        let mut num = n + self.num as i32;
-        let mut new_key = self.date_key.clone();
-        new_key.add_months(-1);
-        let mut max = max_day_of_month(new_key.month, new_key.year);
-        while num < 0 {
-            num += max as i32;
-            new_key.add_months(-1);
-            max = max_day_of_month(new_key.month, new_key.year);
-        }
-        self.date_key = new_key;
-        self.num = max + num as u8;
+    pub(crate) fn from_day_number(n: i64) -> DayDate {
+        let z = n + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let m = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let y = (yoe + era * 400 + if m <= 2 { 1 } else { 0 }) as i32;
+        DayDate { num: d, date_key: DateKey { month: m, year: y } }
     }
 
-    fn append_days(&mut self, n: i32) {
-        assert!(n > 0);
-        let mut max = max_day_of_month(self.date_key.month, self.date_key.year);
-        if n + self.num as i32 <= max as i32 {
-            self.num += n as u8;
-            return;
-        }
-        
-        let rest = max - self.num;
-        let mut num = n - rest as i32;
-        let mut new_key = self.date_key.clone();
-        
-        new_key.add_months(1);
-        max = max_day_of_month(new_key.month, new_key.year);
-        while num > max as i32 {
-            num -= max as i32;
-            new_key.add_months(1);
-            max = max_day_of_month(new_key.month, new_key.year);
-        }
+    pub fn date_key(&self) -> DateKey {
+        self.date_key
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        Weekday::ALL[(self.day_number() + 3).rem_euclid(7) as usize]
+    }
+
+    // signed number of days from self to other
+    pub fn days_between(&self, other: &DayDate) -> i64 {
+        other.day_number() - self.day_number()
+    }
+
+    // ISO 8601 week-numbering year and week
+    pub fn iso_week(&self) -> (i32, u8) {
+        let thursday = DayDate::from_day_number(self.day_number() + 3 - self.weekday() as i64);
+        let first_day = DayDate::new(thursday.year(), 1, 1);
+        (thursday.year(), (first_day.days_between(&thursday) / 7 + 1) as u8)
+    }
+
+    pub fn start_of_month(&self) -> DayDate {
+        self.date_key.first_day()
+    }
+
+    pub fn end_of_month(&self) -> DayDate {
+        self.date_key.last_day()
+    }
 
-        self.date_key = new_key;
-        self.num = num as u8;
+    pub fn is_end_of_month(&self) -> bool {
+        self.num == self.date_key.nb_days()
+    }
+
+    // every day from self to end, both included
+    pub fn range(&self, end: &DayDate) -> DayRange {
+        DayRange { next: self.day_number(), end: end.day_number() }
     }
 
     pub fn parse(string: &str) -> Result<DayDate, DateError> {
@@ -345,6 +418,28 @@ impl DayDate {
     }
 }
 
+pub struct DayRange {
+    next: i64,
+    end: i64
+}
+
+impl Iterator for DayRange {
+    type Item = DayDate;
+
+    fn next(&mut self) -> Option<DayDate> {
+        if self.next > self.end {
+            return None;
+        }
+        self.next += 1;
+        Some(DayDate::from_day_number(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.end - self.next + 1).max(0) as usize;
+        (n, Some(n))
+    }
+}
+
 pub fn is_leap_year(y: i32) -> bool {
     y % 4 == 0 && (y % 100 != 0 || y % 400 == 0)
 }
@@ -412,6 +507,25 @@ mod tests {
         assert_eq!(d.year, 2001);
     }
 
+    #[test]
+    fn add_months_end_of_month() {
+        let mut d = DayDate::new(2000, 1, 31);
+        d.add_months(1);
+        assert_eq!(d, DayDate::new(2000, 2, 29));
+        d.add_months(12);
+        assert_eq!(d, DayDate::new(2001, 2, 28));
+
+        let mut d = DayDate::new(2001, 3, 31);
+        d.add_months(-1);
+        assert_eq!(d, DayDate::new(2001, 2, 28));
+        d.add_months(1);
+        assert_eq!(d, DayDate::new(2001, 3, 28));
+
+        let mut d = DayDate::new(2000, 2, 29);
+        d.add_years(1);
+        assert_eq!(d, DayDate::new(2001, 2, 28));
+    }
+
     #[test]
     fn add_days_tests() {
         let mut d = DayDate::build(2000, 1, 1).unwrap();
@@ -441,6 +555,17 @@ mod tests {
         assert_eq!(d.date_key.year, 2002);
     }
 
+    #[test]
+    fn add_days_across_years() {
+        let mut d = DayDate::new(2000, 1, 31);
+        d.add_days(-10);
+        assert_eq!(d.num, 21);
+        d.add_days(-400);
+        assert_eq!(d, DayDate::new(1998, 12, 17));
+        d.add_days(410);
+        assert_eq!(d, DayDate::new(2000, 1, 31));
+    }
+
     #[test]
     fn invalid_day() {
         let mut x: DayDate = Default::default();
@@ -470,6 +595,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn day_numbers() {
+        assert_eq!(DayDate::new(1970, 1, 1).day_number(), 0);
+        assert_eq!(DayDate::new(2000, 3, 1).day_number(), 11017);
+        assert_eq!(DayDate::from_day_number(11017), DayDate::new(2000, 3, 1));
+        assert_eq!(DayDate::from_day_number(-1), DayDate::new(1969, 12, 31));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(DayDate::new(2024, 2, 3).weekday(), Weekday::Saturday);
+        assert_eq!(DayDate::new(2023, 8, 27).weekday(), Weekday::Sunday);
+        assert_eq!(DayDate::new(1969, 12, 29).weekday(), Weekday::Monday);
+        assert_eq!(Weekday::from_iso(4).unwrap(), Weekday::Thursday);
+        assert_eq!(Weekday::Sunday.iso_number(), 7);
+        assert!(Weekday::from_iso(0).is_err());
+        assert_eq!(Weekday::from_name("Friday").unwrap(), Weekday::Friday);
+        assert_eq!(Weekday::from_name("sat").unwrap(), Weekday::Saturday);
+        assert!(Weekday::from_name("sa").is_err());
+    }
+
+    #[test]
+    fn day_differences() {
+        let d = DayDate::new(2023, 12, 15);
+        assert_eq!(d.days_between(&DayDate::new(2024, 3, 1)), 77);
+        assert_eq!(DayDate::new(2024, 3, 1).days_between(&d), -77);
+        assert_eq!(d.days_between(&d), 0);
+    }
+
+    #[test]
+    fn iso_weeks() {
+        assert_eq!(DayDate::new(2021, 1, 3).iso_week(), (2020, 53));
+        assert_eq!(DayDate::new(2021, 1, 4).iso_week(), (2021, 1));
+        assert_eq!(DayDate::new(2024, 12, 30).iso_week(), (2025, 1));
+        assert_eq!(DayDate::new(2023, 6, 15).iso_week(), (2023, 24));
+    }
+
+    #[test]
+    fn month_bounds() {
+        let d = DayDate::new(2024, 2, 10);
+        assert_eq!(d.start_of_month(), DayDate::new(2024, 2, 1));
+        assert_eq!(d.end_of_month(), DayDate::new(2024, 2, 29));
+        assert!(d.end_of_month().is_end_of_month());
+        assert_eq!(d.date_key(), DateKey::new(2, 2024));
+        assert_eq!(DateKey::new(2, 2023).last_day(), DayDate::new(2023, 2, 28));
+        assert_eq!(DateKey::new(4, 2023).nb_days(), 30);
+    }
+
+    #[test]
+    fn day_ranges() {
+        let days: Vec<DayDate> = DayDate::new(2023, 12, 30).range(&DayDate::new(2024, 1, 2)).collect();
+        assert_eq!(days.len(), 4);
+        assert_eq!(days[2], DayDate::new(2024, 1, 1));
+        assert_eq!(DayDate::new(2024, 1, 2).range(&DayDate::new(2024, 1, 1)).count(), 0);
+    }
+
     #[test]
     fn test_parse() {
         let mut s = "20231010";
@@ -498,4 +679,11 @@ mod tests {
         let e = DayDate::parse(s).err().unwrap();
         assert_eq!(e.details, ERR_INVALID_DATE_STR);
     }
-}
\ No newline at end of file
+}
+
+pub mod business;
+pub mod datetime;
+pub mod format;
+pub mod locale;
+pub mod range;
+pub mod relative;
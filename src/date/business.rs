@@ -0,0 +1,272 @@
+use std::{collections::BTreeSet, fs::File, path::Path};
+
+use json::JsonValue;
+
+use crate::tools::bracket::{BracketSection, Brackets};
+use super::{DateError, DateKey, DayDate, Weekday};
+
+const KEY_WEEKEND: &str = "weekend";
+const KEY_HOLIDAYS: &str = "holidays";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusinessCalendar {
+    weekend: Vec<Weekday>,
+    holidays: BTreeSet<DayDate>
+}
+
+impl Default for BusinessCalendar {
+    fn default() -> Self {
+        BusinessCalendar { weekend: vec![Weekday::Saturday, Weekday::Sunday], holidays: BTreeSet::new() }
+    }
+}
+
+impl BusinessCalendar {
+    // days given twice are kept once
+    pub fn with_weekend(mut self, weekend: Vec<Weekday>) -> Self {
+        self.weekend = Self::distinct(weekend);
+        self
+    }
+
+    pub fn with_holidays(mut self, holidays: &[DayDate]) -> Self {
+        self.holidays.extend(holidays.iter().copied());
+        self
+    }
+
+    pub fn add_holiday(&mut self, date: DayDate) {
+        self.holidays.insert(date);
+    }
+
+    pub fn weekend(&self) -> &[Weekday] {
+        &self.weekend
+    }
+
+    pub fn holidays(&self) -> Vec<DayDate> {
+        self.holidays.iter().copied().collect()
+    }
+
+    pub fn is_business_day(&self, date: &DayDate) -> bool {
+        !self.weekend.contains(&date.weekday()) && !self.holidays.contains(date)
+    }
+
+    // first business day strictly after the date
+    pub fn next_business_day(&self, date: &DayDate) -> Result<DayDate, DateError> {
+        self.step(date, 1)
+    }
+
+    // last business day strictly before the date
+    pub fn previous_business_day(&self, date: &DayDate) -> Result<DayDate, DateError> {
+        self.step(date, -1)
+    }
+
+    pub fn add_business_days(&self, date: &DayDate, n: i32) -> Result<DayDate, DateError> {
+        let mut current = *date;
+        for _ in 0..n.unsigned_abs() {
+            current = self.step(&current, n.signum())?;
+        }
+        Ok(current)
+    }
+
+    // business days after start up to end included, negative when end is before start
+    pub fn business_days_between(&self, start: &DayDate, end: &DayDate) -> i64 {
+        if end < start {
+            return -self.business_days_between(end, start);
+        }
+        let mut first = *start;
+        first.add_days(1);
+        first.range(end).filter(|d| self.is_business_day(d)).count() as i64
+    }
+
+    // n starts at 1 for the first business day of the month
+    pub fn nth_business_day(&self, month: &DateKey, n: u8) -> Option<DayDate> {
+        if n == 0 {
+            return None;
+        }
+        month.first_day()
+            .range(&month.last_day())
+            .filter(|d| self.is_business_day(d))
+            .nth(n as usize - 1)
+    }
+
+    pub fn last_business_day(&self, month: &DateKey) -> Option<DayDate> {
+        month.first_day()
+            .range(&month.last_day())
+            .filter(|d| self.is_business_day(d))
+            .last()
+    }
+
+    fn step(&self, date: &DayDate, direction: i32) -> Result<DayDate, DateError> {
+        if self.weekend.len() >= 7 {
+            return Err(DateError { details: String::from("Calendar has no business days") });
+        }
+        let mut current = *date;
+        loop {
+            current.add_days(direction);
+            if self.is_business_day(&current) {
+                return Ok(current);
+            }
+        }
+    }
+
+    // { "weekend": ["saturday", "sunday"], "holidays": ["2024-01-01", ...] }
+    pub fn from_json(value: &JsonValue) -> Result<BusinessCalendar, DateError> {
+        let mut calendar = BusinessCalendar::default();
+        if !value[KEY_WEEKEND].is_null() {
+            calendar.weekend = Self::read_weekend(&Self::json_strings(value, KEY_WEEKEND)?)?;
+        }
+        if !value[KEY_HOLIDAYS].is_null() {
+            calendar.holidays = Self::read_holidays(&Self::json_strings(value, KEY_HOLIDAYS)?)?;
+        }
+        Ok(calendar)
+    }
+
+    fn json_strings<'a>(value: &'a JsonValue, key: &str) -> Result<Vec<&'a str>, DateError> {
+        let invalid = || DateError { details: format!("Calendar {} has to be an array of strings", key) };
+        if !value[key].is_array() {
+            return Err(invalid());
+        }
+        value[key].members().map(|m| m.as_str().ok_or_else(invalid)).collect()
+    }
+
+    // weekend[,saturday,sunday,] and holidays[,2024-01-01,...,] in the document root
+    pub fn from_brackets(bk: &Brackets) -> Result<BusinessCalendar, DateError> {
+        let mut calendar = BusinessCalendar::default();
+        if let BracketSection::Array(root) = &*bk.root.borrow() {
+            for child in root.borrow().array.iter() {
+                let BracketSection::Array(item) = &**child else { continue };
+                let item = item.borrow();
+                let text = match item.array.first().map(|s| &**s) {
+                    Some(BracketSection::Str(v)) => bk.get_text(&v.borrow()),
+                    _ => continue
+                };
+                let values: Vec<&str> = text.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+                match bk.get_text(&item.name).as_str() {
+                    KEY_WEEKEND => calendar.weekend = Self::read_weekend(&values)?,
+                    KEY_HOLIDAYS => calendar.holidays = Self::read_holidays(&values)?,
+                    _ => {}
+                }
+            }
+        }
+        Ok(calendar)
+    }
+
+    // the file extension tells the format, .json or .bk
+    pub fn load(path: &str) -> Result<BusinessCalendar, DateError> {
+        let read_error = |e: &dyn std::fmt::Display| DateError { details: format!("Unable to read calendar {}: {}", path, e) };
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => {
+                let text = std::fs::read_to_string(path).map_err(|e| read_error(&e))?;
+                let value = json::parse(&text).map_err(|e| read_error(&e))?;
+                Self::from_json(&value)
+            },
+            Some("bk") => {
+                let mut file = File::open(path).map_err(|e| read_error(&e))?;
+                let bk = Brackets::build_from_file_string(&mut file, path).map_err(|e| read_error(&e))?;
+                Self::from_brackets(&bk)
+            },
+            _ => Err(DateError { details: format!("Unsupported calendar file {}", path) })
+        }
+    }
+
+    fn read_weekend(names: &[&str]) -> Result<Vec<Weekday>, DateError> {
+        Ok(Self::distinct(names.iter().map(|n| Weekday::from_name(n)).collect::<Result<_, _>>()?))
+    }
+
+    fn distinct(days: Vec<Weekday>) -> Vec<Weekday> {
+        let mut weekend: Vec<Weekday> = Vec::with_capacity(days.len());
+        for day in days {
+            if !weekend.contains(&day) {
+                weekend.push(day);
+            }
+        }
+        weekend
+    }
+
+    fn read_holidays(dates: &[&str]) -> Result<BTreeSet<DayDate>, DateError> {
+        dates.iter().map(|d| DayDate::parse(d.trim())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_calendar() -> BusinessCalendar {
+        BusinessCalendar::default().with_holidays(&[DayDate::new(2024, 1, 1), DayDate::new(2024, 5, 1)])
+    }
+
+    #[test]
+    fn business_days() {
+        let calendar = build_calendar();
+        assert!(!calendar.is_business_day(&DayDate::new(2024, 1, 1)));
+        assert!(!calendar.is_business_day(&DayDate::new(2024, 1, 6)));
+        assert!(calendar.is_business_day(&DayDate::new(2024, 1, 2)));
+        assert_eq!(calendar.next_business_day(&DayDate::new(2023, 12, 29)).unwrap(), DayDate::new(2024, 1, 2));
+        assert_eq!(calendar.previous_business_day(&DayDate::new(2024, 1, 2)).unwrap(), DayDate::new(2023, 12, 29));
+        assert_eq!(calendar.add_business_days(&DayDate::new(2024, 4, 29), 3).unwrap(), DayDate::new(2024, 5, 3));
+        assert_eq!(calendar.add_business_days(&DayDate::new(2024, 5, 3), -3).unwrap(), DayDate::new(2024, 4, 29));
+    }
+
+    #[test]
+    fn days_between() {
+        let calendar = build_calendar();
+        assert_eq!(calendar.business_days_between(&DayDate::new(2023, 12, 29), &DayDate::new(2024, 1, 5)), 4);
+        assert_eq!(calendar.business_days_between(&DayDate::new(2024, 1, 5), &DayDate::new(2023, 12, 29)), -4);
+        assert_eq!(calendar.business_days_between(&DayDate::new(2024, 1, 5), &DayDate::new(2024, 1, 5)), 0);
+    }
+
+    #[test]
+    fn nth_business_day() {
+        let calendar = build_calendar();
+        let month = DateKey::new(1, 2024);
+        assert_eq!(calendar.nth_business_day(&month, 1), Some(DayDate::new(2024, 1, 2)));
+        assert_eq!(calendar.nth_business_day(&month, 5), Some(DayDate::new(2024, 1, 8)));
+        assert_eq!(calendar.nth_business_day(&month, 0), None);
+        assert_eq!(calendar.nth_business_day(&month, 30), None);
+        assert_eq!(calendar.last_business_day(&DateKey::new(3, 2024)), Some(DayDate::new(2024, 3, 29)));
+    }
+
+    #[test]
+    fn custom_weekend() {
+        let calendar = BusinessCalendar::default().with_weekend(vec![Weekday::Friday, Weekday::Saturday]);
+        assert!(calendar.is_business_day(&DayDate::new(2024, 1, 7)));
+        assert!(!calendar.is_business_day(&DayDate::new(2024, 1, 5)));
+        let calendar = calendar.with_weekend(vec![
+            Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday,
+            Weekday::Friday, Weekday::Saturday, Weekday::Sunday
+        ]);
+        assert!(calendar.next_business_day(&DayDate::new(2024, 1, 5)).is_err());
+
+        // repeated days count once, a business day is left
+        let calendar = calendar.with_weekend(vec![
+            Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday,
+            Weekday::Friday, Weekday::Saturday, Weekday::Saturday
+        ]);
+        assert_eq!(calendar.weekend().len(), 6);
+        assert_eq!(calendar.next_business_day(&DayDate::new(2024, 1, 5)).unwrap(), DayDate::new(2024, 1, 7));
+    }
+
+    #[test]
+    fn load_json() {
+        let value = json::parse(r#"{ "weekend": ["fri", "sat"], "holidays": ["2024-01-01", "25/12/2024"] }"#).unwrap();
+        let calendar = BusinessCalendar::from_json(&value).unwrap();
+        assert_eq!(calendar.weekend(), &[Weekday::Friday, Weekday::Saturday]);
+        assert_eq!(calendar.holidays(), vec![DayDate::new(2024, 1, 1), DayDate::new(2024, 12, 25)]);
+        let value = json::parse(r#"{ "holidays": ["2024-13-01"] }"#).unwrap();
+        assert!(BusinessCalendar::from_json(&value).is_err());
+        let value = json::parse(r#"{ "weekend": "sunday" }"#).unwrap();
+        assert!(BusinessCalendar::from_json(&value).is_err());
+        let value = json::parse(r#"{ "holidays": ["2024-01-01", 20240501] }"#).unwrap();
+        assert!(BusinessCalendar::from_json(&value).is_err());
+        let value = json::parse(r#"{ "weekend": ["sun", "sunday"] }"#).unwrap();
+        assert_eq!(BusinessCalendar::from_json(&value).unwrap().weekend(), &[Weekday::Sunday]);
+    }
+
+    #[test]
+    fn load_brackets() {
+        let text = "@[\n    name[holidays]\n]\n[\n    weekend[,sunday,]\n    holidays[,2024-01-01, 2024-12-25,]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let calendar = BusinessCalendar::from_brackets(&bk).unwrap();
+        assert_eq!(calendar.weekend(), &[Weekday::Sunday]);
+        assert_eq!(calendar.holidays(), vec![DayDate::new(2024, 1, 1), DayDate::new(2024, 12, 25)]);
+    }
+}
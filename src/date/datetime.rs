@@ -0,0 +1,251 @@
+use std::{cmp::Ordering, time::{Duration, SystemTime}};
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use super::{DateError, DayDate};
+
+const SECONDS_IN_DAY: i64 = 86400;
+const MAX_OFFSET_MINUTES: i16 = 18 * 60;
+
+lazy_static! {
+    // ISO 8601 extended format, RFC 3339 being a subset of it
+    static ref RGX_DATETIME: Regex = Regex::new(concat!(
+        r"^(?<y>\d{4})-(?<m>\d{2})-(?<d>\d{2})",
+        r"(?:[Tt ](?<h>\d{2}):(?<mi>\d{2})(?::(?<s>\d{2})(?:[.,](?<f>\d{1,9}))?)?",
+        r"(?<z>[Zz]|(?<sign>[+-])(?<oh>\d{2}):?(?<om>\d{2}))?)?$"
+    )).unwrap();
+}
+
+// instant with a UTC offset, ordered and compared by instant
+#[derive(Clone, Copy, Debug)]
+pub struct DateTime {
+    date: DayDate,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+    offset: i16
+}
+
+impl DateTime {
+    pub fn build(date: DayDate, hour: u8, minute: u8, second: u8) -> Result<DateTime, DateError> {
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(DateError { details: format!("Invalid time {:02}:{:02}:{:02}", hour, minute, second) });
+        }
+        Ok(DateTime { date, hour, minute, second, nanosecond: 0, offset: 0 })
+    }
+
+    // offset from UTC in minutes, the local time is kept
+    pub fn with_offset(mut self, offset: i16) -> Result<DateTime, DateError> {
+        if offset.abs() > MAX_OFFSET_MINUTES {
+            return Err(DateError { details: format!("Offset has to be between -{} and {} minutes", MAX_OFFSET_MINUTES, MAX_OFFSET_MINUTES) });
+        }
+        self.offset = offset;
+        Ok(self)
+    }
+
+    pub fn with_nanosecond(mut self, nanosecond: u32) -> Result<DateTime, DateError> {
+        if nanosecond >= 1_000_000_000 {
+            return Err(DateError { details: String::from("Nanoseconds have to be lower than one second") });
+        }
+        self.nanosecond = nanosecond;
+        Ok(self)
+    }
+
+    pub fn date(&self) -> &DayDate {
+        &self.date
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    pub fn offset(&self) -> i16 {
+        self.offset
+    }
+
+    // seconds since 1970-01-01T00:00:00Z
+    pub fn timestamp(&self) -> i64 {
+        self.date.day_number() * SECONDS_IN_DAY
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+            - self.offset as i64 * 60
+    }
+
+    pub fn from_timestamp(seconds: i64, nanosecond: u32) -> DateTime {
+        let date = DayDate::from_day_number(seconds.div_euclid(SECONDS_IN_DAY));
+        let rest = seconds.rem_euclid(SECONDS_IN_DAY);
+        DateTime {
+            date,
+            hour: (rest / 3600) as u8,
+            minute: (rest % 3600 / 60) as u8,
+            second: (rest % 60) as u8,
+            nanosecond: nanosecond % 1_000_000_000,
+            offset: 0
+        }
+    }
+
+    pub fn now() -> DateTime {
+        Self::from_system_time(SystemTime::now())
+    }
+
+    pub fn from_system_time(time: SystemTime) -> DateTime {
+        match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(d) => Self::from_timestamp(d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                match d.subsec_nanos() {
+                    0 => Self::from_timestamp(-(d.as_secs() as i64), 0),
+                    n => Self::from_timestamp(-(d.as_secs() as i64) - 1, 1_000_000_000 - n)
+                }
+            }
+        }
+    }
+
+    pub fn to_system_time(&self) -> SystemTime {
+        let seconds = self.timestamp();
+        if seconds >= 0 {
+            return SystemTime::UNIX_EPOCH + Duration::new(seconds as u64, self.nanosecond);
+        }
+        SystemTime::UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs()) + Duration::from_nanos(self.nanosecond as u64)
+    }
+
+    // same instant expressed with another offset
+    pub fn to_offset(&self, offset: i16) -> Result<DateTime, DateError> {
+        let local = Self::from_timestamp(self.timestamp() + offset as i64 * 60, self.nanosecond);
+        local.with_offset(offset)
+    }
+
+    pub fn to_utc(&self) -> DateTime {
+        Self::from_timestamp(self.timestamp(), self.nanosecond)
+    }
+
+    pub fn parse(s: &str) -> Result<DateTime, DateError> {
+        let captures = match RGX_DATETIME.captures(s.trim()) {
+            Some(c) => c,
+            None => return Err(DateError { details: format!("Invalid datetime format '{}'", s) })
+        };
+        let number = |name: &str| captures.name(name).map_or(0, |m| m.as_str().parse::<u32>().unwrap_or(0));
+        let date = DayDate::build(number("y") as i32, number("m") as u8, number("d") as u8)?;
+        let mut datetime = DateTime::build(date, number("h") as u8, number("mi") as u8, number("s") as u8)?;
+        if let Some(f) = captures.name("f") {
+            datetime = datetime.with_nanosecond(number("f") * 10u32.pow(9 - f.len() as u32))?;
+        }
+        if captures.name("sign").is_some() {
+            let offset = (number("oh") * 60 + number("om")) as i16;
+            datetime = datetime.with_offset(if &captures["sign"] == "-" { -offset } else { offset })?;
+        }
+        Ok(datetime)
+    }
+
+    pub fn to_rfc3339(&self) -> String {
+        let mut s = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.date.year(), self.date.month(), self.date.day(), self.hour, self.minute, self.second
+        );
+        if self.nanosecond > 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            s.push('.');
+            s.push_str(fraction.trim_end_matches('0'));
+        }
+        if self.offset == 0 {
+            s.push('Z');
+        }
+        else {
+            let sign = if self.offset < 0 { '-' } else { '+' };
+            s.push_str(&format!("{}{:02}:{:02}", sign, self.offset.abs() / 60, self.offset.abs() % 60));
+        }
+        s
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_rfc3339())
+    }
+}
+
+impl PartialEq for DateTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DateTime {}
+
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DateTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.timestamp(), self.nanosecond).cmp(&(other.timestamp(), other.nanosecond))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rfc3339() {
+        let d = DateTime::parse("2023-10-10T08:30:15+02:00").unwrap();
+        assert_eq!(*d.date(), DayDate::new(2023, 10, 10));
+        assert_eq!((d.hour(), d.minute(), d.second(), d.offset()), (8, 30, 15, 120));
+        assert_eq!(d.to_rfc3339(), "2023-10-10T08:30:15+02:00");
+        let d = DateTime::parse("2023-10-10t06:30:15.250Z").unwrap();
+        assert_eq!(d.nanosecond(), 250_000_000);
+        assert_eq!(d.to_string(), "2023-10-10T06:30:15.25Z");
+    }
+
+    #[test]
+    fn parse_iso8601() {
+        let d = DateTime::parse("2023-10-10 08:30-0330").unwrap();
+        assert_eq!((d.second(), d.offset()), (0, -210));
+        let d = DateTime::parse("2023-10-10").unwrap();
+        assert_eq!((d.hour(), d.offset()), (0, 0));
+        assert!(DateTime::parse("2023-10-10T25:00:00Z").is_err());
+        assert!(DateTime::parse("2023-02-30T10:00:00Z").is_err());
+        assert!(DateTime::parse("10/10/2023 08:30").is_err());
+        assert!(DateTime::parse("2023-10-10T08:30+19:00").is_err());
+    }
+
+    #[test]
+    fn timestamps() {
+        let d = DateTime::parse("2023-10-10T08:30:15+02:00").unwrap();
+        assert_eq!(d.timestamp(), 1696919415);
+        assert_eq!(DateTime::from_timestamp(1696919415, 0), d);
+        assert_eq!(DateTime::from_timestamp(-1, 0).to_string(), "1969-12-31T23:59:59Z");
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1696919415, 5);
+        assert_eq!(DateTime::from_system_time(time).to_system_time(), time);
+        let time = SystemTime::UNIX_EPOCH - Duration::new(10, 5);
+        assert_eq!(DateTime::from_system_time(time).to_system_time(), time);
+    }
+
+    #[test]
+    fn offsets_and_ordering() {
+        let paris = DateTime::parse("2023-10-10T08:30:00+02:00").unwrap();
+        let utc = paris.to_utc();
+        assert_eq!(utc.to_string(), "2023-10-10T06:30:00Z");
+        assert_eq!(paris, utc);
+        assert_eq!(utc.to_offset(-300).unwrap().to_string(), "2023-10-10T01:30:00-05:00");
+        let later = DateTime::parse("2023-10-10T07:00:00+00:00").unwrap();
+        assert!(paris < later);
+        assert!(DateTime::parse("2023-10-10T08:00:00-01:00").unwrap() > later);
+    }
+}
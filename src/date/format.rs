@@ -0,0 +1,222 @@
+use super::{locale::Locale, DateError, DateKey, DayDate};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Literal(char),
+    Year,
+    ShortYear,
+    Month,
+    MonthNoPad,
+    Day,
+    DayNoPad,
+    ShortMonthName,
+    MonthName
+}
+
+// supported patterns: %Y, %y, %m, %-m, %d, %-d, %b, %B and %%
+fn tokenize(pattern: &str) -> Result<Vec<Token>, DateError> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            tokens.push(Token::Literal(c));
+            continue;
+        }
+        let token = match chars.next() {
+            Some('Y') => Token::Year,
+            Some('y') => Token::ShortYear,
+            Some('m') => Token::Month,
+            Some('d') => Token::Day,
+            Some('b') => Token::ShortMonthName,
+            Some('B') => Token::MonthName,
+            Some('%') => Token::Literal('%'),
+            Some('-') => match chars.next() {
+                Some('m') => Token::MonthNoPad,
+                Some('d') => Token::DayNoPad,
+                _ => return Err(invalid_pattern(pattern))
+            },
+            _ => return Err(invalid_pattern(pattern))
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn invalid_pattern(pattern: &str) -> DateError {
+    DateError { details: format!("Invalid date pattern '{}'", pattern) }
+}
+
+fn format(year: i32, month: u8, day: Option<u8>, pattern: &str, locale: Locale) -> Result<String, DateError> {
+    let mut s = String::new();
+    for token in tokenize(pattern)? {
+        match token {
+            Token::Literal(c) => s.push(c),
+            Token::Year => s.push_str(&format!("{:04}", year)),
+            Token::ShortYear => s.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Token::Month => s.push_str(&format!("{:02}", month)),
+            Token::MonthNoPad => s.push_str(&month.to_string()),
+            Token::ShortMonthName => s.push_str(locale.short_month_name(month)),
+            Token::MonthName => s.push_str(locale.month_name(month)),
+            Token::Day | Token::DayNoPad => {
+                let d = match day {
+                    Some(d) => d,
+                    None => return Err(DateError { details: String::from("Day pattern used on a month date") })
+                };
+                if token == Token::Day {
+                    s.push_str(&format!("{:02}", d));
+                }
+                else {
+                    s.push_str(&d.to_string());
+                }
+            }
+        }
+    }
+    Ok(s)
+}
+
+fn parse(s: &str, pattern: &str, locale: Locale) -> Result<(i32, u8, Option<u8>), DateError> {
+    let err = || DateError { details: format!("'{}' does not match pattern '{}'", s, pattern) };
+    let (mut year, mut month, mut day): (Option<i32>, Option<u8>, Option<u8>) = (None, None, None);
+    let mut rest = s;
+    for token in tokenize(pattern)? {
+        match token {
+            Token::Literal(c) => {
+                rest = rest.strip_prefix(c).ok_or_else(err)?;
+            },
+            Token::Year => {
+                let (v, r) = take_digits(rest, 4, 4).ok_or_else(err)?;
+                year = Some(v as i32);
+                rest = r;
+            },
+            Token::ShortYear => {
+                let (v, r) = take_digits(rest, 2, 2).ok_or_else(err)?;
+                year = Some(2000 + v as i32);
+                rest = r;
+            },
+            Token::Month | Token::MonthNoPad => {
+                let (v, r) = take_digits(rest, 1, 2).ok_or_else(err)?;
+                month = Some(v as u8);
+                rest = r;
+            },
+            Token::Day | Token::DayNoPad => {
+                let (v, r) = take_digits(rest, 1, 2).ok_or_else(err)?;
+                day = Some(v as u8);
+                rest = r;
+            },
+            Token::ShortMonthName | Token::MonthName => {
+                let names = if token == Token::MonthName { locale.months() } else { locale.short_months() };
+                // the prefix of the input as long as the name is compared, then skipped
+                let prefix_end = |n: &str| rest.char_indices().nth(n.chars().count()).map_or(rest.len(), |(i, _)| i);
+                // longest names first, so that "mars" is not read as "mar"
+                let (index, end) = names.iter()
+                    .enumerate()
+                    .map(|(i, n)| (i, n, prefix_end(n)))
+                    .filter(|(_, n, end)| rest[..*end].to_lowercase() == n.to_lowercase())
+                    .max_by_key(|(_, n, _)| n.chars().count())
+                    .map(|(i, _, end)| (i, end))
+                    .ok_or_else(err)?;
+                month = Some(index as u8 + 1);
+                rest = &rest[end..];
+            }
+        }
+    }
+    if !rest.is_empty() {
+        return Err(err());
+    }
+    match (year, month) {
+        (Some(y), Some(m)) => Ok((y, m, day)),
+        _ => Err(DateError { details: format!("Pattern '{}' has to include a year and a month", pattern) })
+    }
+}
+
+fn take_digits(s: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+    let len = s.chars().take(max).take_while(|c| c.is_ascii_digit()).count();
+    if len < min {
+        return None;
+    }
+    Some((s[..len].parse().ok()?, &s[len..]))
+}
+
+impl DayDate {
+    pub fn format(&self, pattern: &str) -> Result<String, DateError> {
+        self.format_with_locale(pattern, Locale::default())
+    }
+
+    pub fn format_with_locale(&self, pattern: &str, locale: Locale) -> Result<String, DateError> {
+        format(self.year(), self.month(), Some(self.day()), pattern, locale)
+    }
+
+    pub fn parse_with_format(s: &str, pattern: &str) -> Result<DayDate, DateError> {
+        Self::parse_with_format_locale(s, pattern, Locale::default())
+    }
+
+    pub fn parse_with_format_locale(s: &str, pattern: &str, locale: Locale) -> Result<DayDate, DateError> {
+        match parse(s, pattern, locale)? {
+            (y, m, Some(d)) => DayDate::build(y, m, d),
+            _ => Err(DateError { details: format!("Pattern '{}' has to include a day", pattern) })
+        }
+    }
+}
+
+impl DateKey {
+    pub fn format(&self, pattern: &str) -> Result<String, DateError> {
+        self.format_with_locale(pattern, Locale::default())
+    }
+
+    pub fn format_with_locale(&self, pattern: &str, locale: Locale) -> Result<String, DateError> {
+        format(self.year(), self.month(), None, pattern, locale)
+    }
+
+    pub fn parse_with_format(s: &str, pattern: &str) -> Result<DateKey, DateError> {
+        Self::parse_with_format_locale(s, pattern, Locale::default())
+    }
+
+    pub fn parse_with_format_locale(s: &str, pattern: &str, locale: Locale) -> Result<DateKey, DateError> {
+        let (y, m, _) = parse(s, pattern, locale)?;
+        DateKey::build(m, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_days() {
+        let d = DayDate::new(2021, 3, 4);
+        assert_eq!(d.format("%Y-%m-%d").unwrap(), "2021-03-04");
+        assert_eq!(d.format("%d/%m/%y").unwrap(), "04/03/21");
+        assert_eq!(d.format("%-d %B %Y").unwrap(), "4 March 2021");
+        assert_eq!(d.format_with_locale("%-d %b %Y", Locale::Fr).unwrap(), "4 mars 2021");
+        assert_eq!(d.format("100%%").unwrap(), "100%");
+        assert!(d.format("%Q").is_err());
+    }
+
+    #[test]
+    fn format_months() {
+        let d = DateKey::new(8, 2020);
+        assert_eq!(d.format("%b-%Y").unwrap(), "Aug-2020");
+        assert_eq!(d.format("%-m/%Y").unwrap(), "8/2020");
+        assert!(d.format("%d/%m/%Y").is_err());
+    }
+
+    #[test]
+    fn parse_explicit_format() {
+        assert_eq!(DayDate::parse_with_format("03/04/2021", "%d/%m/%Y").unwrap(), DayDate::new(2021, 4, 3));
+        assert_eq!(DayDate::parse_with_format("03/04/2021", "%m/%d/%Y").unwrap(), DayDate::new(2021, 3, 4));
+        assert_eq!(DayDate::parse_with_format("4 march 2021", "%-d %B %Y").unwrap(), DayDate::new(2021, 3, 4));
+        assert_eq!(DayDate::parse_with_format_locale("12 févr. 21", "%d %b %y", Locale::Fr).unwrap(), DayDate::new(2021, 2, 12));
+        assert_eq!(DateKey::parse_with_format("Aug-2020", "%b-%Y").unwrap(), DateKey::new(8, 2020));
+        assert_eq!(DayDate::parse_with_format_locale("12 FÉVR. 21", "%d %b %y", Locale::Fr).unwrap(), DayDate::new(2021, 2, 12));
+        assert_eq!(DateKey::parse_with_format_locale("AOÛT 2020", "%B %Y", Locale::Fr).unwrap(), DateKey::new(8, 2020));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(DayDate::parse_with_format("31/02/2021", "%d/%m/%Y").is_err());
+        assert!(DayDate::parse_with_format("2021-03-04", "%d/%m/%Y").is_err());
+        assert!(DayDate::parse_with_format("03/2021", "%m/%Y").is_err());
+        assert!(DayDate::parse_with_format("03/04/2021 extra", "%d/%m/%Y").is_err());
+        assert!(DateKey::parse_with_format("2021", "%Y").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+const MONTHS_EN: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December"
+];
+const SHORT_MONTHS_EN: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"
+];
+const MONTHS_FR: [&str; 12] = [
+    "janvier", "février", "mars", "avril", "mai", "juin",
+    "juillet", "août", "septembre", "octobre", "novembre", "décembre"
+];
+const SHORT_MONTHS_FR: [&str; 12] = [
+    "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.", "déc."
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Fr
+}
+
+impl Locale {
+    pub fn month_name(&self, m: u8) -> &'static str {
+        self.months()[m as usize - 1]
+    }
+
+    pub fn short_month_name(&self, m: u8) -> &'static str {
+        self.short_months()[m as usize - 1]
+    }
+
+    pub fn months(&self) -> &'static [&'static str; 12] {
+        match self {
+            Locale::En => &MONTHS_EN,
+            Locale::Fr => &MONTHS_FR
+        }
+    }
+
+    pub fn short_months(&self) -> &'static [&'static str; 12] {
+        match self {
+            Locale::En => &SHORT_MONTHS_EN,
+            Locale::Fr => &SHORT_MONTHS_FR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_names() {
+        assert_eq!(Locale::En.month_name(1), "January");
+        assert_eq!(Locale::En.short_month_name(8), "Aug");
+        assert_eq!(Locale::Fr.month_name(8), "août");
+        assert_eq!(Locale::Fr.short_month_name(2), "févr.");
+    }
+}
@@ -0,0 +1,183 @@
+use super::{DateError, DateKey};
+
+const MONTHS_IN_LTM: i32 = 12;
+
+// inclusive range of months
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateKeyRange {
+    start: DateKey,
+    end: DateKey
+}
+
+impl DateKeyRange {
+    pub fn build(start: DateKey, end: DateKey) -> Result<DateKeyRange, DateError> {
+        if end < start {
+            return Err(DateError { details: format!("Range end {} is before its start {}", end.to_string(), start.to_string()) });
+        }
+        Ok(DateKeyRange { start, end })
+    }
+
+    pub fn single(date: DateKey) -> DateKeyRange {
+        DateKeyRange { start: date, end: date }
+    }
+
+    // LTM window ending at the given month, the month twelve months before is included
+    pub fn ltm(end: &DateKey) -> DateKeyRange {
+        let mut start = *end;
+        start.add_months(-MONTHS_IN_LTM);
+        DateKeyRange { start, end: *end }
+    }
+
+    pub fn start(&self) -> &DateKey {
+        &self.start
+    }
+
+    pub fn end(&self) -> &DateKey {
+        &self.end
+    }
+
+    pub fn nb_months(&self) -> usize {
+        (index(&self.end) - index(&self.start) + 1) as usize
+    }
+
+    pub fn contains(&self, date: &DateKey) -> bool {
+        self.start <= *date && date <= &self.end
+    }
+
+    pub fn iter(&self) -> DateKeyIter {
+        DateKeyIter { next: index(&self.start), end: index(&self.end) }
+    }
+
+    pub fn intersection(&self, other: &DateKeyRange) -> Option<DateKeyRange> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        DateKeyRange::build(start, end).ok()
+    }
+
+    // consecutive ranges of the given size, the last one may be shorter
+    pub fn chunks(&self, size: usize) -> Vec<DateKeyRange> {
+        if size == 0 {
+            return vec![];
+        }
+        let months: Vec<DateKey> = self.iter().collect();
+        months.chunks(size)
+            .map(|c| DateKeyRange { start: c[0], end: c[c.len() - 1] })
+            .collect()
+    }
+
+    // smallest list of ranges covering the months, duplicates are ignored
+    pub fn from_months(months: &[DateKey]) -> Vec<DateKeyRange> {
+        let mut sorted = months.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        let mut ranges: Vec<DateKeyRange> = vec![];
+        for m in sorted {
+            match ranges.last_mut() {
+                Some(r) if index(&r.end) + 1 == index(&m) => r.end = m,
+                _ => ranges.push(DateKeyRange::single(m))
+            }
+        }
+        ranges
+    }
+
+    // missing months between the first and the last of the months
+    pub fn gaps(months: &[DateKey]) -> Vec<DateKeyRange> {
+        Self::from_months(months).windows(2)
+            .map(|w| DateKeyRange { start: from_index(index(&w[0].end) + 1), end: from_index(index(&w[1].start) - 1) })
+            .collect()
+    }
+}
+
+impl IntoIterator for DateKeyRange {
+    type Item = DateKey;
+    type IntoIter = DateKeyIter;
+
+    fn into_iter(self) -> DateKeyIter {
+        self.iter()
+    }
+}
+
+pub struct DateKeyIter {
+    next: i32,
+    end: i32
+}
+
+impl Iterator for DateKeyIter {
+    type Item = DateKey;
+
+    fn next(&mut self) -> Option<DateKey> {
+        if self.next > self.end {
+            return None;
+        }
+        self.next += 1;
+        Some(from_index(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.end - self.next + 1).max(0) as usize;
+        (n, Some(n))
+    }
+}
+
+fn index(date: &DateKey) -> i32 {
+    date.year() * 12 + date.month() as i32 - 1
+}
+
+fn from_index(i: i32) -> DateKey {
+    DateKey::new((i.rem_euclid(12) + 1) as u8, i.div_euclid(12))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterate() {
+        let range = DateKeyRange::build(DateKey::new(11, 2022), DateKey::new(2, 2023)).unwrap();
+        let months: Vec<DateKey> = range.iter().collect();
+        assert_eq!(months, vec![DateKey::new(11, 2022), DateKey::new(12, 2022), DateKey::new(1, 2023), DateKey::new(2, 2023)]);
+        assert_eq!(range.nb_months(), 4);
+        assert!(range.contains(&DateKey::new(12, 2022)));
+        assert!(!range.contains(&DateKey::new(3, 2023)));
+        assert!(DateKeyRange::build(DateKey::new(3, 2023), DateKey::new(2, 2023)).is_err());
+    }
+
+    #[test]
+    fn ltm_window() {
+        let range = DateKeyRange::ltm(&DateKey::new(6, 2023));
+        assert_eq!(*range.start(), DateKey::new(6, 2022));
+        assert_eq!(range.nb_months(), 13);
+    }
+
+    #[test]
+    fn intersection() {
+        let a = DateKeyRange::build(DateKey::new(1, 2023), DateKey::new(6, 2023)).unwrap();
+        let b = DateKeyRange::build(DateKey::new(4, 2023), DateKey::new(12, 2023)).unwrap();
+        let c = DateKeyRange::build(DateKey::new(7, 2023), DateKey::new(12, 2023)).unwrap();
+        assert_eq!(a.intersection(&b), Some(DateKeyRange::build(DateKey::new(4, 2023), DateKey::new(6, 2023)).unwrap()));
+        assert_eq!(a.intersection(&c), None);
+    }
+
+    #[test]
+    fn chunks() {
+        let range = DateKeyRange::build(DateKey::new(9, 2022), DateKey::new(4, 2023)).unwrap();
+        let chunks = range.chunks(3);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(*chunks[1].start(), DateKey::new(12, 2022));
+        assert_eq!(chunks[2].nb_months(), 2);
+        assert!(range.chunks(0).is_empty());
+    }
+
+    #[test]
+    fn gaps() {
+        let months = vec![
+            DateKey::new(1, 2023), DateKey::new(2, 2023), DateKey::new(5, 2023),
+            DateKey::new(2, 2023), DateKey::new(6, 2023), DateKey::new(8, 2023)
+        ];
+        assert_eq!(DateKeyRange::from_months(&months).len(), 3);
+        let gaps = DateKeyRange::gaps(&months);
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0], DateKeyRange::build(DateKey::new(3, 2023), DateKey::new(4, 2023)).unwrap());
+        assert_eq!(gaps[1], DateKeyRange::single(DateKey::new(7, 2023)));
+    }
+}
@@ -0,0 +1,153 @@
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::fiscalyear::FiscalYear;
+use super::{DateError, DateKey, DayDate};
+
+lazy_static! {
+    static ref RGX_RELATIVE: Regex = Regex::new(r"^(?<base>.*?)\s*(?<offsets>(?:[+-]\s*\d+\s*[dwmy]\s*)*)$").unwrap();
+    static ref RGX_OFFSET: Regex = Regex::new(r"(?<sign>[+-])\s*(?<n>\d+)\s*(?<unit>[dwmy])").unwrap();
+    static ref RGX_BOUND: Regex = Regex::new(r"^(?<bound>start|end) of (?:(?<shift>this|last|previous|next) )?(?<unit>month|quarter|year|fy)$").unwrap();
+    static ref RGX_FY_BOUND: Regex = Regex::new(r"^fy (?<bound>start|end)$").unwrap();
+}
+
+impl DayDate {
+    // absolute dates as in parse, or expressions like "today", "end of last month",
+    // "fy start", "+3m" or "start of next quarter -2d", resolved against the reference date
+    pub fn parse_relative(s: &str, reference: &DayDate, years: &[FiscalYear]) -> Result<DayDate, DateError> {
+        if let Ok(d) = DayDate::parse(s.trim()) {
+            return Ok(d);
+        }
+        let text = s.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase();
+        let captures = match RGX_RELATIVE.captures(&text) {
+            Some(c) => c,
+            None => return Err(invalid_expression(s))
+        };
+        let mut date = resolve_base(&captures["base"], reference, years).ok_or_else(|| invalid_expression(s))?;
+        for offset in RGX_OFFSET.captures_iter(&captures["offsets"]) {
+            let mut n: i32 = offset["n"].parse().map_err(|_| invalid_expression(s))?;
+            if &offset["sign"] == "-" {
+                n = -n;
+            }
+            match &offset["unit"] {
+                "d" => date.add_days(n),
+                "w" => date.add_days(n * 7),
+                "m" => date.add_months(n),
+                _ => date.add_years(n)
+            }
+        }
+        Ok(date)
+    }
+}
+
+fn invalid_expression(s: &str) -> DateError {
+    DateError { details: format!("Invalid date expression '{}'", s) }
+}
+
+fn resolve_base(base: &str, reference: &DayDate, years: &[FiscalYear]) -> Option<DayDate> {
+    match base {
+        "" | "today" => return Some(*reference),
+        "yesterday" | "tomorrow" => {
+            let mut d = *reference;
+            d.add_days(if base == "yesterday" { -1 } else { 1 });
+            return Some(d);
+        },
+        _ => {}
+    }
+    if let Ok(d) = DayDate::parse(base) {
+        return Some(d);
+    }
+    let (bound, shift, unit) = if let Some(c) = RGX_FY_BOUND.captures(base) {
+        (c["bound"].to_owned(), 0, String::from("fy"))
+    }
+    else {
+        let c = RGX_BOUND.captures(base)?;
+        let shift = match c.name("shift").map(|m| m.as_str()) {
+            Some("last") | Some("previous") => -1,
+            Some("next") => 1,
+            _ => 0
+        };
+        (c["bound"].to_owned(), shift, c["unit"].to_owned())
+    };
+    let month = reference.date_key();
+    let (start, end) = match unit.as_str() {
+        "month" => {
+            let mut start = month;
+            start.add_months(shift);
+            (start, start)
+        },
+        "quarter" => {
+            let mut start = DateKey::new((month.month() - 1) / 3 * 3 + 1, month.year());
+            start.add_months(shift * 3);
+            let mut end = start;
+            end.add_months(2);
+            (start, end)
+        },
+        "year" => (DateKey::new(1, month.year() + shift), DateKey::new(12, month.year() + shift)),
+        _ => {
+            let position = years.iter().position(|fy| fy.range().is_ok_and(|r| r.contains(&month)))?;
+            let fy = years.get(usize::try_from(position as i32 + shift).ok()?)?;
+            (*fy.min().ok()?, *fy.max().ok()?)
+        }
+    };
+    Some(if bound == "start" { start.first_day() } else { end.last_day() })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use super::*;
+
+    fn reference() -> DayDate {
+        DayDate::new(2024, 5, 15)
+    }
+
+    fn build_years() -> Vec<FiscalYear> {
+        (2023..=2025).map(|y| {
+            let mut months: Vec<DateKey> = (7..=12).map(|m| DateKey::new(m, y - 1)).collect();
+            months.extend((1..=6).map(|m| DateKey::new(m, y)));
+            FiscalYear::build(Rc::new(months))
+        }).collect()
+    }
+
+    fn parse(s: &str) -> Result<DayDate, DateError> {
+        DayDate::parse_relative(s, &reference(), &build_years())
+    }
+
+    #[test]
+    fn simple_expressions() {
+        assert_eq!(parse("today").unwrap(), reference());
+        assert_eq!(parse(" Yesterday ").unwrap(), DayDate::new(2024, 5, 14));
+        assert_eq!(parse("2023-01-10").unwrap(), DayDate::new(2023, 1, 10));
+        assert_eq!(parse("+3m").unwrap(), DayDate::new(2024, 8, 15));
+        assert_eq!(parse("-2w +1d").unwrap(), DayDate::new(2024, 5, 2));
+        assert_eq!(parse("2024-01-31 +1m").unwrap(), DayDate::new(2024, 2, 29));
+    }
+
+    #[test]
+    fn period_bounds() {
+        assert_eq!(parse("end of last month").unwrap(), DayDate::new(2024, 4, 30));
+        assert_eq!(parse("start of month").unwrap(), DayDate::new(2024, 5, 1));
+        assert_eq!(parse("start of next quarter").unwrap(), DayDate::new(2024, 7, 1));
+        assert_eq!(parse("end of previous quarter").unwrap(), DayDate::new(2024, 3, 31));
+        assert_eq!(parse("end of this year").unwrap(), DayDate::new(2024, 12, 31));
+        assert_eq!(parse("end of last month + 10d").unwrap(), DayDate::new(2024, 5, 10));
+    }
+
+    #[test]
+    fn fiscal_bounds() {
+        assert_eq!(parse("FY start").unwrap(), DayDate::new(2023, 7, 1));
+        assert_eq!(parse("fy end").unwrap(), DayDate::new(2024, 6, 30));
+        assert_eq!(parse("end of last fy").unwrap(), DayDate::new(2023, 6, 30));
+        assert_eq!(parse("start of next fy").unwrap(), DayDate::new(2024, 7, 1));
+        assert!(DayDate::parse_relative("fy start", &reference(), &[]).is_err());
+        assert!(DayDate::parse_relative("end of next fy", &DayDate::new(2025, 1, 1), &build_years()).is_err());
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert!(parse("someday").is_err());
+        assert!(parse("+3x").is_err());
+        assert!(parse("end of the world").is_err());
+    }
+}
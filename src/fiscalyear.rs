@@ -1,11 +1,61 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::HashMap, error::Error, fmt::{Display, Formatter}, rc::Rc};
 
-use crate::{date::DateKey, indic::{FY, LTM}, compute::ComputeKey};
+use crate::{date::{range::DateKeyRange, DateKey}, indic::{FY, LTM}, compute::ComputeKey};
 
 
 pub struct FiscalYear {
     slices: HashMap<u8, Vec<DateKey>>,
-    months: Rc<Vec<DateKey>>
+    months: Rc<Vec<DateKey>>,
+    bounds: Option<DateKeyRange>
+}
+
+// issues refer to years by their position in the validated list
+#[derive(Debug, Clone, PartialEq)]
+pub enum YearIssue {
+    Empty { year: usize },
+    DuplicateMonth { year: usize, month: DateKey },
+    NonConsecutive { year: usize, missing: DateKeyRange },
+    Overlap { first: usize, second: usize, months: DateKeyRange },
+    Gap { first: usize, second: usize, missing: DateKeyRange },
+    Unordered { first: usize, second: usize }
+}
+
+impl Display for YearIssue {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let range = |r: &DateKeyRange| format!("{} to {}", r.start().to_string(), r.end().to_string());
+        match self {
+            YearIssue::Empty { year } => write!(f, "Fiscal year {} has no months", year),
+            YearIssue::DuplicateMonth { year, month } => write!(f, "Fiscal year {} repeats month {}", year, month.to_string()),
+            YearIssue::NonConsecutive { year, missing } => write!(f, "Fiscal year {} misses months {}", year, range(missing)),
+            YearIssue::Overlap { first, second, months } => write!(f, "Fiscal years {} and {} overlap from {}", first, second, range(months)),
+            YearIssue::Gap { first, second, missing } => write!(f, "Months {} are missing between fiscal years {} and {}", range(missing), first, second),
+            YearIssue::Unordered { first, second } => write!(f, "Fiscal year {} starts before fiscal year {}", second, first)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FiscalYearError {
+    issues: Vec<YearIssue>
+}
+
+impl FiscalYearError {
+    pub fn issues(&self) -> &[YearIssue] {
+        &self.issues
+    }
+}
+
+impl Error for FiscalYearError {
+    fn description(&self) -> &str {
+        "Invalid fiscal years"
+    }
+}
+
+impl Display for FiscalYearError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let issues: Vec<String> = self.issues.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", issues.join("; "))
+    }
 }
 
 impl FiscalYear {
@@ -14,7 +64,11 @@ impl FiscalYear {
         if mths.len() > MAX_MONTHS as usize {
             panic!("Fiscal year does not allow having more than {} months", MAX_MONTHS);
         }
-        FiscalYear { slices: HashMap::new(), months: mths }
+        let bounds = match (mths.iter().min(), mths.iter().max()) {
+            (Some(min), Some(max)) => Some(DateKeyRange::build(*min, *max).unwrap()),
+            _ => None
+        };
+        FiscalYear { slices: HashMap::new(), months: mths, bounds }
     }
     pub fn get_months(&self) -> Vec<DateKey> {
         self.months.as_ref().to_vec()
@@ -23,21 +77,23 @@ impl FiscalYear {
         self.months.len() as u8
     }
     pub fn min(&self) -> Result<&DateKey, &'static str> {
-        let mut v: Vec<&DateKey> = self.months.iter().collect();
-        v.sort();
-        match v.first() {
-            Some(m) => Ok(*m),
+        match &self.bounds {
+            Some(r) => Ok(r.start()),
             None => Err("Empty year")
         }
     }
     pub fn max(&self) -> Result<&DateKey, &'static str> {
-        let mut v: Vec<&DateKey> = self.months.iter().collect();
-        v.sort();
-        match v.last() {
-            Some(m) => Ok(*m),
+        match &self.bounds {
+            Some(r) => Ok(r.end()),
             None => Err("Empty year")
         }
     }
+    pub fn range(&self) -> Result<DateKeyRange, &'static str> {
+        self.bounds.ok_or("Empty year")
+    }
+    pub fn gaps(&self) -> Vec<DateKeyRange> {
+        DateKeyRange::gaps(&self.months)
+    }
     pub fn get_slice(&self, pos: u8) -> Result<Vec<DateKey>, &'static str> {
         if pos < 1 {
             return Err("Invalid position: expected a position greater than zero");
@@ -60,12 +116,26 @@ impl FiscalYear {
         }
     }
 
+    pub fn find_slice_position(&self, date: &DateKey) -> Result<u8, &'static str> {
+        let pos = self.slices.iter()
+            .find(|x| x.1.iter().any(|d| d == date))
+            .map(|x| *x.0);
+
+        match pos {
+            Some(p) => Ok(p),
+            None => Err("Slice not found")
+        }
+    }
+
+    pub fn nb_slices(&self) -> u8 {
+        self.slices.len() as u8
+    }
+
     pub fn find_ltm_slice(list: &Vec<Self>, end_date: &DateKey) -> Result<Vec<DateKey>, &'static str> {
-        let mut start_date = *end_date;
-        start_date.add_months(-12);
+        let ltm = DateKeyRange::ltm(end_date);
 
         let _s: Vec<_> = list.iter().flat_map(|y| y.slices.iter().map(|x| x.1))
-            .filter(|&s| s.iter().any(|d| d == end_date || d == &start_date))
+            .filter(|&s| s.iter().any(|d| d == ltm.end() || d == ltm.start()))
             .collect();
 
         if _s.len() == 0 {
@@ -75,7 +145,7 @@ impl FiscalYear {
         let mut dates: Vec<DateKey> = list.iter()
             .flat_map(|y| y.months.as_ref())
             .map(|m| *m)
-            .filter(|m| start_date <= *m && m <= end_date)
+            .filter(|m| ltm.contains(m))
             .collect();
 
         dates.extend(_s.iter().flat_map(|s| *s));
@@ -87,6 +157,50 @@ impl FiscalYear {
         Err("Slice not found")
     }
 
+    pub fn validate(years: &[Self]) -> Result<(), FiscalYearError> {
+        let mut issues: Vec<YearIssue> = vec![];
+        for (year, fy) in years.iter().enumerate() {
+            if fy.months.is_empty() {
+                issues.push(YearIssue::Empty { year });
+            }
+            let mut months = fy.get_months();
+            months.sort();
+            months.windows(2)
+                .filter(|w| w[0] == w[1])
+                .for_each(|w| issues.push(YearIssue::DuplicateMonth { year, month: w[0] }));
+            fy.gaps().into_iter().for_each(|missing| issues.push(YearIssue::NonConsecutive { year, missing }));
+        }
+        let mut ranges: Vec<(usize, DateKeyRange)> = years.iter()
+            .enumerate()
+            .filter_map(|(i, fy)| Some((i, fy.bounds?)))
+            .collect();
+        ranges.windows(2)
+            .filter(|w| w[1].1.start() < w[0].1.start())
+            .for_each(|w| issues.push(YearIssue::Unordered { first: w[0].0, second: w[1].0 }));
+        // overlaps and gaps are looked for in time order, against the year ending last so far
+        ranges.sort_by_key(|(i, r)| (*r.start(), *i));
+        let mut last: Option<(usize, DateKeyRange)> = None;
+        for (second, b) in ranges {
+            let Some((first, a)) = last else {
+                last = Some((second, b));
+                continue;
+            };
+            if let Some(months) = a.intersection(&b) {
+                issues.push(YearIssue::Overlap { first, second, months });
+            }
+            else if let Some(missing) = DateKeyRange::gaps(&[*a.end(), *b.start()]).pop() {
+                issues.push(YearIssue::Gap { first, second, missing });
+            }
+            if b.end() > a.end() {
+                last = Some((second, b));
+            }
+        }
+        if issues.is_empty() {
+            return Ok(());
+        }
+        Err(FiscalYearError { issues })
+    }
+
     pub fn find<'a>(v: &'a Vec<Self>, d: &DateKey) -> Result<&'a FiscalYear, Result<(), &'static str>> {
         let _y = v.iter().find(|fy| fy.min() <= Ok(d) && Ok(d) <= fy.max());
         match _y {
@@ -201,6 +315,57 @@ mod tests {
         assert_eq!(DateKey::new(12, 2023), *fy.max().unwrap())
     }
 
+    #[test]
+    fn fy_range_and_gaps() {
+        let mut months: Vec<DateKey> = (1..=4).map(|m| DateKey::new(m, 2023)).collect();
+        months.extend((7..=12).map(|m| DateKey::new(m, 2023)));
+        let fy = FiscalYear::build(Rc::new(months));
+        assert_eq!(fy.range().unwrap().nb_months(), 12);
+        assert_eq!(fy.gaps(), vec![DateKeyRange::build(DateKey::new(5, 2023), DateKey::new(6, 2023)).unwrap()]);
+        assert!(FiscalYear::build(Rc::new(vec![])).range().is_err());
+    }
+
+    #[test]
+    fn validate_years() {
+        let years: Vec<FiscalYear> = (2021..=2023)
+            .map(|y| FiscalYear::build(Rc::new((1..=12).map(|m| DateKey::new(m, y)).collect())))
+            .collect();
+        assert!(FiscalYear::validate(&years).is_ok());
+        assert!(FiscalYear::validate(&[]).is_ok());
+    }
+
+    #[test]
+    fn validate_invalid_years() {
+        let years = vec![
+            FiscalYear::build(Rc::new(vec![DateKey::new(1, 2022), DateKey::new(2, 2022), DateKey::new(2, 2022), DateKey::new(4, 2022)])),
+            FiscalYear::build(Rc::new(vec![DateKey::new(4, 2022), DateKey::new(5, 2022)])),
+            FiscalYear::build(Rc::new(vec![DateKey::new(8, 2022)])),
+            FiscalYear::build(Rc::new(vec![])),
+            FiscalYear::build(Rc::new(vec![DateKey::new(1, 2021)]))
+        ];
+        let error = FiscalYear::validate(&years).err().unwrap();
+        assert_eq!(error.issues(), &[
+            YearIssue::DuplicateMonth { year: 0, month: DateKey::new(2, 2022) },
+            YearIssue::NonConsecutive { year: 0, missing: DateKeyRange::single(DateKey::new(3, 2022)) },
+            YearIssue::Empty { year: 3 },
+            YearIssue::Unordered { first: 2, second: 4 },
+            YearIssue::Gap { first: 4, second: 0, missing: DateKeyRange::build(DateKey::new(2, 2021), DateKey::new(12, 2021)).unwrap() },
+            YearIssue::Overlap { first: 0, second: 1, months: DateKeyRange::single(DateKey::new(4, 2022)) },
+            YearIssue::Gap { first: 1, second: 2, missing: DateKeyRange::build(DateKey::new(6, 2022), DateKey::new(7, 2022)).unwrap() }
+        ]);
+        assert!(error.to_string().starts_with("Fiscal year 0 repeats month 2/2022; "));
+    }
+
+    #[test]
+    fn validate_unordered_years() {
+        let year = |y: i32| FiscalYear::build(Rc::new((1..=12).map(|m| DateKey::new(m, y)).collect()));
+        let error = FiscalYear::validate(&[year(2022), year(2024), year(2023)]).err().unwrap();
+        assert_eq!(error.issues(), &[YearIssue::Unordered { first: 1, second: 2 }]);
+        let long = FiscalYear::build(Rc::new((2021..=2022).flat_map(|y| (1..=12).map(move |m| DateKey::new(m, y))).collect()));
+        let error = FiscalYear::validate(&[long, year(2022), year(2023)]).err().unwrap();
+        assert_eq!(error.issues(), &[YearIssue::Overlap { first: 0, second: 1, months: year(2022).range().unwrap() }]);
+    }
+
     #[test]
     #[should_panic(expected="Date was not found in any fiscal years")]
     fn find_none_before() {
@@ -322,4 +487,8 @@ mod tests {
         assert!(!r.iter().any(|d| d.month() == 3 && d.year() == 2021));
     }
 
-}
\ No newline at end of file
+}
+
+pub mod calendar;
+pub mod label;
+pub mod period;
@@ -0,0 +1,223 @@
+use std::rc::Rc;
+
+use json::{object, JsonValue};
+
+use crate::date::{range::DateKeyRange, DateKey};
+use super::{label::{LabelFormat, YearStyle}, FiscalYear};
+
+const DEFAULT_SLICE_SIZE: u8 = 3;
+const FIRST_YEAR_SHORT: &str = "short";
+const FIRST_YEAR_LONG: &str = "long";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FirstYear {
+    Short,
+    Long
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiscalCalendar {
+    first_month: DateKey,
+    year_end_month: u8,
+    last_month: DateKey,
+    first_year: FirstYear,
+    slice_size: u8
+}
+
+impl FiscalCalendar {
+    pub fn build(first_month: DateKey, year_end_month: u8, last_month: DateKey) -> Result<FiscalCalendar, &'static str> {
+        if !(1..=12).contains(&year_end_month) {
+            return Err("Year end month has to be between 1 and 12");
+        }
+        if last_month < first_month {
+            return Err("Last month of the calendar is before its first month");
+        }
+        Ok(FiscalCalendar {
+            first_month,
+            year_end_month,
+            last_month,
+            first_year: FirstYear::Short,
+            slice_size: DEFAULT_SLICE_SIZE
+        })
+    }
+
+    pub fn with_first_year(mut self, first_year: FirstYear) -> Self {
+        self.first_year = first_year;
+        self
+    }
+
+    pub fn with_slice_size(mut self, size: u8) -> Self {
+        self.slice_size = size;
+        self
+    }
+
+    pub fn first_month(&self) -> &DateKey {
+        &self.first_month
+    }
+
+    pub fn last_month(&self) -> &DateKey {
+        &self.last_month
+    }
+
+    pub fn year_end_month(&self) -> u8 {
+        self.year_end_month
+    }
+
+    pub fn generate(&self) -> Vec<FiscalYear> {
+        let mut years: Vec<FiscalYear> = vec![];
+        let mut months: Vec<DateKey> = vec![];
+        // a long first year skips its first closing
+        let mut skip_closing = self.first_year == FirstYear::Long && self.first_month.month() != self.year_end_month % 12 + 1;
+        for current in DateKeyRange::build(self.first_month, self.last_month).unwrap() {
+            months.push(current);
+            if current.month() == self.year_end_month {
+                if skip_closing {
+                    skip_closing = false;
+                }
+                else {
+                    years.push(self.build_year(std::mem::take(&mut months)));
+                }
+            }
+        }
+        if !months.is_empty() {
+            years.push(self.build_year(months));
+        }
+        years
+    }
+
+    pub fn year_label(&self, date: &DateKey) -> Option<String> {
+        let years = self.generate();
+        FiscalYear::find(&years, date).ok().map(Self::label_of)
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.generate().iter().map(Self::label_of).collect()
+    }
+
+    pub fn label_of(fy: &FiscalYear) -> String {
+        let format = LabelFormat { year_style: YearStyle::Split, ..Default::default() };
+        format.fiscal_year(fy)
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            first_month: Self::date_to_json(&self.first_month),
+            year_end_month: self.year_end_month,
+            last_month: Self::date_to_json(&self.last_month),
+            first_year: match self.first_year {
+                FirstYear::Short => FIRST_YEAR_SHORT,
+                FirstYear::Long => FIRST_YEAR_LONG
+            },
+            slice_size: self.slice_size
+        }
+    }
+
+    pub fn from_json(value: &JsonValue) -> Result<FiscalCalendar, &'static str> {
+        let first_month = Self::date_from_json(&value["first_month"])?;
+        let last_month = Self::date_from_json(&value["last_month"])?;
+        let year_end_month = value["year_end_month"].as_u8().ok_or("Invalid year end month")?;
+        let first_year = match value["first_year"].as_str() {
+            None | Some(FIRST_YEAR_SHORT) => FirstYear::Short,
+            Some(FIRST_YEAR_LONG) => FirstYear::Long,
+            _ => return Err("Invalid first year mode")
+        };
+        let slice_size = match &value["slice_size"] {
+            JsonValue::Null => DEFAULT_SLICE_SIZE,
+            v => v.as_u8().ok_or("Invalid slice size")?
+        };
+        Ok(FiscalCalendar::build(first_month, year_end_month, last_month)?
+            .with_first_year(first_year)
+            .with_slice_size(slice_size))
+    }
+
+    pub fn parse(text: &str) -> Result<FiscalCalendar, &'static str> {
+        match json::parse(text) {
+            Ok(v) => Self::from_json(&v),
+            Err(_) => Err("Unable to read calendar")
+        }
+    }
+
+    fn build_year(&self, months: Vec<DateKey>) -> FiscalYear {
+        let size = self.slice_size.min(months.len() as u8);
+        let mut fy = FiscalYear::build(Rc::new(months));
+        if size > 0 {
+            fy.build_slices(size);
+        }
+        fy
+    }
+
+    fn date_to_json(date: &DateKey) -> JsonValue {
+        object! { month: date.month(), year: date.year() }
+    }
+
+    fn date_from_json(value: &JsonValue) -> Result<DateKey, &'static str> {
+        let month = value["month"].as_u8().ok_or("Invalid month")?;
+        let year = value["year"].as_i32().ok_or("Invalid year")?;
+        DateKey::build(month, year).map_err(|_| "Invalid month")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_first_year() {
+        let calendar = FiscalCalendar::build(DateKey::new(3, 2019), 8, DateKey::new(8, 2021)).unwrap();
+        let years = calendar.generate();
+        assert_eq!(years.len(), 3);
+        assert_eq!(years[0].nb_months(), 6);
+        assert_eq!(*years[1].min().unwrap(), DateKey::new(9, 2019));
+        assert_eq!(*years[2].max().unwrap(), DateKey::new(8, 2021));
+        assert_eq!(FiscalYear::get_slices(&years[1]).len(), 4);
+        assert_eq!(FiscalYear::get_slices(&years[0]).len(), 2);
+    }
+
+    #[test]
+    fn long_first_year() {
+        let calendar = FiscalCalendar::build(DateKey::new(3, 2019), 8, DateKey::new(10, 2021))
+            .unwrap()
+            .with_first_year(FirstYear::Long);
+        let years = calendar.generate();
+        assert_eq!(years.len(), 3);
+        assert_eq!(years[0].nb_months(), 18);
+        assert_eq!(*years[0].max().unwrap(), DateKey::new(8, 2020));
+        assert_eq!(years[2].nb_months(), 2);
+    }
+
+    #[test]
+    fn full_first_year() {
+        let calendar = FiscalCalendar::build(DateKey::new(1, 2020), 12, DateKey::new(12, 2021))
+            .unwrap()
+            .with_first_year(FirstYear::Long);
+        let years = calendar.generate();
+        assert_eq!(years.len(), 2);
+        assert_eq!(years[0].nb_months(), 12);
+    }
+
+    #[test]
+    fn labels() {
+        let calendar = FiscalCalendar::build(DateKey::new(3, 2019), 8, DateKey::new(8, 2021)).unwrap();
+        assert_eq!(calendar.labels(), vec!["FY2019", "FY2019/20", "FY2020/21"]);
+        assert_eq!(calendar.year_label(&DateKey::new(1, 2021)), Some(String::from("FY2020/21")));
+        assert_eq!(calendar.year_label(&DateKey::new(1, 2030)), None);
+    }
+
+    #[test]
+    fn invalid_calendar() {
+        assert!(FiscalCalendar::build(DateKey::new(3, 2019), 13, DateKey::new(8, 2021)).is_err());
+        assert!(FiscalCalendar::build(DateKey::new(3, 2019), 8, DateKey::new(8, 2018)).is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let calendar = FiscalCalendar::build(DateKey::new(3, 2019), 8, DateKey::new(8, 2021))
+            .unwrap()
+            .with_first_year(FirstYear::Long)
+            .with_slice_size(4);
+        let text = calendar.to_json().dump();
+        let parsed = FiscalCalendar::parse(&text).unwrap();
+        assert_eq!(parsed, calendar);
+        assert!(FiscalCalendar::parse("{\"year_end_month\": 8}").is_err());
+    }
+}
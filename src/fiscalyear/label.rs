@@ -0,0 +1,158 @@
+use crate::{compute::ComputeKey, date::{locale::Locale, DateKey}, indic::{FY, LTM, SLC, YTD}};
+use super::FiscalYear;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YearStyle {
+    Full,
+    Short,
+    Split
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YearReference {
+    Start,
+    End
+}
+
+#[derive(Debug, Clone)]
+pub struct LabelFormat {
+    pub locale: Locale,
+    pub year_style: YearStyle,
+    pub year_reference: YearReference,
+    pub fy_prefix: String,
+    pub ltm_prefix: String,
+    pub ytd_prefix: String,
+    pub quarter_prefix: String,
+    pub half_prefix: String,
+    pub slice_prefix: String,
+    pub month_separator: String
+}
+
+impl Default for LabelFormat {
+    fn default() -> Self {
+        LabelFormat {
+            locale: Default::default(),
+            year_style: YearStyle::Full,
+            year_reference: YearReference::End,
+            fy_prefix: String::from("FY"),
+            ltm_prefix: String::from("LTM"),
+            ytd_prefix: String::from("YTD"),
+            quarter_prefix: String::from("Q"),
+            half_prefix: String::from("H"),
+            slice_prefix: String::from("S"),
+            month_separator: String::from("-")
+        }
+    }
+}
+
+impl LabelFormat {
+    pub fn label(&self, key: &ComputeKey, fy: &FiscalYear) -> Result<String, &'static str> {
+        match key.span {
+            None => Ok(self.month(&key.date)),
+            Some(FY) => Ok(self.fiscal_year(fy)),
+            Some(SLC) => Ok(format!("{} {}", self.slice(&key.date, fy)?, self.fiscal_year(fy))),
+            Some(LTM) => Ok(format!("{} {}", self.ltm_prefix, self.month(&key.date))),
+            Some(YTD) => Ok(format!("{} {}", self.ytd_prefix, self.month(&key.date))),
+            _ => Err("Unknown span")
+        }
+    }
+
+    pub fn month(&self, date: &DateKey) -> String {
+        format!("{}{}{}", self.locale.short_month_name(date.month()), self.month_separator, date.year())
+    }
+
+    pub fn fiscal_year(&self, fy: &FiscalYear) -> String {
+        let (start, end) = match (fy.min(), fy.max()) {
+            (Ok(s), Ok(e)) => (s.year(), e.year()),
+            _ => return self.fy_prefix.clone()
+        };
+        let year = match self.year_reference {
+            YearReference::Start => start,
+            YearReference::End => end
+        };
+        match self.year_style {
+            YearStyle::Full => format!("{}{}", self.fy_prefix, year),
+            YearStyle::Short => format!("{}{:02}", self.fy_prefix, year.rem_euclid(100)),
+            YearStyle::Split if start != end => format!("{}{}/{:02}", self.fy_prefix, start, end.rem_euclid(100)),
+            YearStyle::Split => format!("{}{}", self.fy_prefix, start)
+        }
+    }
+
+    pub fn slice(&self, date: &DateKey, fy: &FiscalYear) -> Result<String, &'static str> {
+        let position = fy.find_slice_position(date)?;
+        // the months per slice tell the prefix, a short year keeps quarters
+        let prefix = match FiscalYear::get_slices(fy).iter().map(|s| s.len()).max() {
+            Some(3) => &self.quarter_prefix,
+            Some(6) => &self.half_prefix,
+            _ => &self.slice_prefix
+        };
+        Ok(format!("{}{}", prefix, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use super::*;
+
+    #[test]
+    fn default_labels() {
+        let fy = build_year(3);
+        let format = LabelFormat::default();
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(12, 2020), span: Some(SLC) }, &fy).unwrap(), "Q2 FY2021");
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(6, 2021), span: Some(FY) }, &fy).unwrap(), "FY2021");
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(8, 2020), span: Some(LTM) }, &fy).unwrap(), "LTM Aug-2020");
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(3, 2021), span: Some(YTD) }, &fy).unwrap(), "YTD Mar-2021");
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(3, 2021), span: None }, &fy).unwrap(), "Mar-2021");
+    }
+
+    #[test]
+    fn halves_and_year_styles() {
+        let fy = build_year(6);
+        let format = LabelFormat { year_style: YearStyle::Short, ..Default::default() };
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(12, 2020), span: Some(SLC) }, &fy).unwrap(), "H1 FY21");
+        let format = LabelFormat { year_style: YearStyle::Split, ..Default::default() };
+        assert_eq!(format.fiscal_year(&fy), "FY2020/21");
+        let format = LabelFormat { year_reference: YearReference::Start, ..Default::default() };
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(2, 2021), span: Some(SLC) }, &fy).unwrap(), "H2 FY2020");
+    }
+
+    #[test]
+    fn localized_labels() {
+        let fy = build_year(3);
+        let format = LabelFormat {
+            locale: Locale::Fr,
+            quarter_prefix: String::from("T"),
+            month_separator: String::from(" "),
+            ..Default::default()
+        };
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(8, 2020), span: Some(LTM) }, &fy).unwrap(), "LTM août 2020");
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(1, 2021), span: Some(SLC) }, &fy).unwrap(), "T3 FY2021");
+    }
+
+    #[test]
+    fn slice_not_found() {
+        let fy = build_year(3);
+        let format = LabelFormat::default();
+        assert!(format.label(&ComputeKey { date: DateKey::new(12, 2022), span: Some(SLC) }, &fy).is_err());
+        assert!(format.label(&ComputeKey { date: DateKey::new(12, 2022), span: Some("Other") }, &fy).is_err());
+    }
+
+    #[test]
+    fn short_year_quarters() {
+        let months: Vec<DateKey> = (1..=6).map(|m| DateKey::new(m, 2021)).collect();
+        let mut fy = FiscalYear::build(Rc::new(months));
+        fy.build_slices(3);
+        let format = LabelFormat::default();
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(5, 2021), span: Some(SLC) }, &fy).unwrap(), "Q2 FY2021");
+        assert_eq!(format.label(&ComputeKey { date: DateKey::new(8, 2020), span: Some(SLC) }, &build_year(2)).unwrap(), "S1 FY2021");
+    }
+
+    fn build_year(slice_size: u8) -> FiscalYear {
+        let mut months: Vec<DateKey> = (7..=12).map(|m| DateKey::new(m, 2020)).collect();
+        months.extend((1..=6).map(|m| DateKey::new(m, 2021)));
+        let mut fy = FiscalYear::build(Rc::new(months));
+        fy.build_slices(slice_size);
+        fy
+    }
+}
@@ -0,0 +1,320 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::date::{DateKey, DayDate, Weekday};
+use super::FiscalYear;
+
+const DAYS_IN_WEEK: i64 = 7;
+const PERIODS_IN_QUARTER: u8 = 3;
+const PERIODS_IN_LTM: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YearEndRule {
+    // last given weekday of the year end month
+    LastWeekday,
+    // given weekday nearest to the end of the year end month
+    NearestWeekday
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeriodPattern {
+    P445,
+    P454,
+    P544
+}
+
+impl PeriodPattern {
+    pub fn weeks(&self) -> [u8; 3] {
+        match self {
+            PeriodPattern::P445 => [4, 4, 5],
+            PeriodPattern::P454 => [4, 5, 4],
+            PeriodPattern::P544 => [5, 4, 4]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FiscalPeriod {
+    index: u8,
+    start: DayDate,
+    end: DayDate
+}
+
+impl FiscalPeriod {
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn start(&self) -> &DayDate {
+        &self.start
+    }
+
+    pub fn end(&self) -> &DayDate {
+        &self.end
+    }
+
+    pub fn nb_days(&self) -> u16 {
+        (self.start.days_between(&self.end) + 1) as u16
+    }
+
+    pub fn nb_weeks(&self) -> u8 {
+        (self.nb_days() as i64 / DAYS_IN_WEEK) as u8
+    }
+
+    pub fn contains(&self, date: &DayDate) -> bool {
+        self.start <= *date && date <= &self.end
+    }
+
+    // the month holding the middle day of the period, the period stands for this month when
+    // the year is computed as a month based one
+    pub fn date_key(&self) -> DateKey {
+        let mut middle = self.start;
+        middle.add_days((self.start.days_between(&self.end) / 2) as i32);
+        middle.date_key()
+    }
+}
+
+pub struct WeekFiscalYear {
+    year: i32,
+    periods: Vec<FiscalPeriod>,
+    slices: HashMap<u8, Vec<u8>>
+}
+
+impl WeekFiscalYear {
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn start(&self) -> &DayDate {
+        self.periods[0].start()
+    }
+
+    pub fn end(&self) -> &DayDate {
+        self.periods[self.periods.len() - 1].end()
+    }
+
+    pub fn nb_weeks(&self) -> u8 {
+        self.periods.iter().map(|p| p.nb_weeks()).sum()
+    }
+
+    pub fn periods(&self) -> &Vec<FiscalPeriod> {
+        &self.periods
+    }
+
+    pub fn get_period(&self, index: u8) -> Result<&FiscalPeriod, &'static str> {
+        match self.periods.iter().find(|p| p.index == index) {
+            Some(p) => Ok(p),
+            None => Err("Invalid period index")
+        }
+    }
+
+    pub fn find_period(&self, date: &DayDate) -> Result<&FiscalPeriod, &'static str> {
+        match self.periods.iter().find(|p| p.contains(date)) {
+            Some(p) => Ok(p),
+            None => Err("Period not found")
+        }
+    }
+
+    pub fn get_slice(&self, pos: u8) -> Result<Vec<FiscalPeriod>, &'static str> {
+        match self.slices.get(&pos) {
+            Some(s) => Ok(s.iter().map(|i| self.periods[*i as usize - 1]).collect()),
+            None => Err("Invalid position")
+        }
+    }
+
+    pub fn find_slice(&self, date: &DayDate) -> Result<Vec<FiscalPeriod>, &'static str> {
+        let index = self.find_period(date)?.index;
+        match self.slices.iter().find(|s| s.1.contains(&index)) {
+            Some(s) => self.get_slice(*s.0),
+            None => Err("Slice not found")
+        }
+    }
+
+    pub fn nb_slices(&self) -> u8 {
+        self.slices.len() as u8
+    }
+
+    pub fn build_slices(&mut self, size: u8) {
+        if size < 1 {
+            panic!("Unable to create slice of size 0");
+        }
+        if size as usize > self.periods.len() {
+            panic!("{}", format!("Too big slice size ({size})"));
+        }
+        let indexes: Vec<u8> = self.periods.iter().map(|p| p.index).collect();
+        self.slices = indexes.chunks(size as usize)
+            .enumerate()
+            .map(|(x, c)| (x as u8 + 1, c.to_vec()))
+            .collect();
+    }
+
+    pub fn find<'a>(years: &'a [Self], date: &DayDate) -> Result<&'a WeekFiscalYear, &'static str> {
+        match years.iter().find(|y| y.start() <= date && date <= y.end()) {
+            Some(y) => Ok(y),
+            None => Err("Date was not found in any fiscal years")
+        }
+    }
+
+    // the period holding the date and the twelve periods before it, the same window as the
+    // month LTM (DateKeyRange::ltm) which includes the month twelve months before
+    pub fn find_ltm_periods(years: &[Self], end_date: &DayDate) -> Result<Vec<FiscalPeriod>, &'static str> {
+        let periods: Vec<&FiscalPeriod> = years.iter().flat_map(|y| y.periods.iter()).collect();
+        let position = match periods.iter().position(|p| p.contains(end_date)) {
+            Some(p) => p,
+            None => return Err("LTM date not found")
+        };
+        if position < PERIODS_IN_LTM {
+            return Err("Not enough periods for LTM");
+        }
+        Ok(periods[position - PERIODS_IN_LTM..=position].iter().map(|p| **p).collect())
+    }
+
+    // month based year for the compute path, which only knows months: each period is
+    // approximated by the month of its middle day (FiscalPeriod::date_key), so a five week
+    // period weighs as much as a four week one and the period dates are not kept
+    pub fn to_fiscal_year(&self) -> FiscalYear {
+        let months: Vec<DateKey> = self.periods.iter().map(|p| p.date_key()).collect();
+        let mut fy = FiscalYear::build(Rc::new(months));
+        let mut slices: Vec<_> = self.slices.iter().collect();
+        slices.sort_by_key(|s| s.0);
+        for (pos, indexes) in slices {
+            fy.slices.insert(*pos, indexes.iter().map(|i| self.periods[*i as usize - 1].date_key()).collect());
+        }
+        fy
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeekCalendar {
+    year_end_month: u8,
+    week_end: Weekday,
+    rule: YearEndRule,
+    pattern: PeriodPattern
+}
+
+impl WeekCalendar {
+    pub fn build(year_end_month: u8, week_end: Weekday, rule: YearEndRule, pattern: PeriodPattern) -> Result<WeekCalendar, &'static str> {
+        if !(1..=12).contains(&year_end_month) {
+            return Err("Year end month has to be between 1 and 12");
+        }
+        Ok(WeekCalendar { year_end_month, week_end, rule, pattern })
+    }
+
+    pub fn year_end(&self, year: i32) -> DayDate {
+        let last_day = DateKey::new(self.year_end_month, year).last_day();
+        let back = (last_day.weekday() as i64 - self.week_end as i64).rem_euclid(DAYS_IN_WEEK);
+        let offset = match self.rule {
+            YearEndRule::NearestWeekday if back > 3 => DAYS_IN_WEEK - back,
+            _ => -back
+        };
+        let mut year_end = last_day;
+        year_end.add_days(offset as i32);
+        year_end
+    }
+
+    // the fiscal year ending in the year end month of the given year
+    pub fn year(&self, year: i32) -> WeekFiscalYear {
+        let mut start = self.year_end(year - 1).day_number() + 1;
+        let end = self.year_end(year).day_number();
+        let extra_week = (end - start + 1) / DAYS_IN_WEEK > 52;
+        let weeks = self.pattern.weeks();
+        let mut periods: Vec<FiscalPeriod> = vec![];
+        for index in 1..=(PERIODS_IN_QUARTER * 4) {
+            let mut nb_weeks = weeks[(index as usize - 1) % 3] as i64;
+            if extra_week && index == PERIODS_IN_QUARTER * 4 {
+                nb_weeks += 1;
+            }
+            let period_end = start + nb_weeks * DAYS_IN_WEEK - 1;
+            periods.push(FiscalPeriod {
+                index,
+                start: DayDate::from_day_number(start),
+                end: DayDate::from_day_number(period_end)
+            });
+            start = period_end + 1;
+        }
+        let mut fy = WeekFiscalYear { year, periods, slices: HashMap::new() };
+        fy.build_slices(PERIODS_IN_QUARTER);
+        fy
+    }
+
+    pub fn generate(&self, first_year: i32, last_year: i32) -> Vec<WeekFiscalYear> {
+        (first_year..=last_year).map(|y| self.year(y)).collect()
+    }
+
+    pub fn find_year(&self, date: &DayDate) -> WeekFiscalYear {
+        let year = if date.month() > self.year_end_month { date.year() + 1 } else { date.year() };
+        let fy = self.year(year);
+        if date > fy.end() {
+            return self.year(year + 1);
+        }
+        if date < fy.start() {
+            return self.year(year - 1);
+        }
+        fy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_year_end() {
+        let calendar = WeekCalendar::build(1, Weekday::Saturday, YearEndRule::NearestWeekday, PeriodPattern::P445).unwrap();
+        assert_eq!(calendar.year_end(2023), DayDate::new(2023, 1, 28));
+        assert_eq!(calendar.year_end(2024), DayDate::new(2024, 2, 3));
+        let fy = calendar.year(2024);
+        assert_eq!(*fy.start(), DayDate::new(2023, 1, 29));
+        assert_eq!(fy.nb_weeks(), 53);
+        assert_eq!(fy.get_period(12).unwrap().nb_weeks(), 6);
+        assert_eq!(calendar.year(2023).nb_weeks(), 52);
+    }
+
+    #[test]
+    fn last_weekday_year_end() {
+        let calendar = WeekCalendar::build(8, Weekday::Saturday, YearEndRule::LastWeekday, PeriodPattern::P454).unwrap();
+        assert_eq!(calendar.year_end(2023), DayDate::new(2023, 8, 26));
+        let fy = calendar.year(2023);
+        let weeks: Vec<u8> = fy.periods().iter().take(3).map(|p| p.nb_weeks()).collect();
+        assert_eq!(weeks, vec![4, 5, 4]);
+        assert_eq!(fy.periods().iter().map(|p| p.nb_days()).sum::<u16>(), 364);
+    }
+
+    #[test]
+    fn periods_and_slices() {
+        let calendar = WeekCalendar::build(1, Weekday::Saturday, YearEndRule::NearestWeekday, PeriodPattern::P445).unwrap();
+        let fy = calendar.find_year(&DayDate::new(2023, 5, 10));
+        assert_eq!(fy.year(), 2024);
+        let period = fy.find_period(&DayDate::new(2023, 5, 10)).unwrap();
+        assert_eq!(period.index(), 4);
+        assert_eq!(*period.start(), DayDate::new(2023, 4, 30));
+        assert_eq!(period.date_key(), DateKey::new(5, 2023));
+        let slice = fy.find_slice(&DayDate::new(2023, 5, 10)).unwrap();
+        assert_eq!(slice.iter().map(|p| p.index()).collect::<Vec<u8>>(), vec![4, 5, 6]);
+        assert_eq!(fy.nb_slices(), 4);
+        assert!(fy.get_slice(5).is_err());
+    }
+
+    #[test]
+    fn ltm_periods() {
+        let calendar = WeekCalendar::build(1, Weekday::Saturday, YearEndRule::NearestWeekday, PeriodPattern::P445).unwrap();
+        let years = calendar.generate(2023, 2024);
+        let ltm = WeekFiscalYear::find_ltm_periods(&years, &DayDate::new(2023, 5, 10)).unwrap();
+        assert_eq!(ltm.len(), 13);
+        assert_eq!(ltm[0].index(), 4);
+        assert_eq!(*ltm[0].start(), DayDate::new(2022, 5, 1));
+        assert_eq!(ltm[12].index(), 4);
+        assert!(WeekFiscalYear::find_ltm_periods(&years, &DayDate::new(2022, 5, 10)).is_err());
+        assert!(WeekFiscalYear::find_ltm_periods(&years, &DayDate::new(2030, 5, 10)).is_err());
+    }
+
+    #[test]
+    fn month_based_year() {
+        let calendar = WeekCalendar::build(1, Weekday::Saturday, YearEndRule::NearestWeekday, PeriodPattern::P445).unwrap();
+        let fy = calendar.year(2024).to_fiscal_year();
+        assert_eq!(fy.nb_months(), 12);
+        assert_eq!(*fy.min().unwrap(), DateKey::new(2, 2023));
+        assert_eq!(*fy.max().unwrap(), DateKey::new(1, 2024));
+        assert_eq!(fy.nb_slices(), 4);
+        assert_eq!(fy.find_slice_position(&DateKey::new(5, 2023)), Ok(2));
+    }
+}
@@ -43,11 +43,22 @@ impl Default for IndicatorName {
 // }
 
 
+#[derive(Debug, Clone)]
+pub enum Weighting {
+    Indicator(&'static isize),
+    DaysInMonth
+}
+
 #[derive(Debug, Clone)]
 pub enum ComputerMode {
     Default,
+    First,
+    Min,
+    Max,
+    Median,
     AddUp,
     Avg,
+    WeightedAvg(Weighting),
     Complex(fn(Vec<ComputeItem>) -> f64)
 }
 
@@ -68,11 +79,45 @@ impl ComputerMode {
         
         match self {
             Self::Default => Ok(*values.last().unwrap()),
+            Self::First => Ok(*inputs[0]),
+            Self::Min => Ok(values.fold(f64::INFINITY, |a, b| a.min(*b))),
+            Self::Max => Ok(values.fold(f64::NEG_INFINITY, |a, b| a.max(*b))),
+            Self::Median => {
+                let mut sorted: Vec<f64> = values.copied().collect();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                let middle = length / 2;
+                if length.is_multiple_of(2) {
+                    Ok((sorted[middle - 1] + sorted[middle]) / 2.0)
+                }
+                else {
+                    Ok(sorted[middle])
+                }
+            },
             Self::AddUp => Ok(FSum::new().add_all(values).value()),
             Self::Avg => { Ok(FSum::new().add_all(values).value() / length as f64) },
+            Self::WeightedAvg(_) => Err(ComputeError::new("Weights are required for a weighted average".to_string())),
             Self::Complex(_) => Err(ComputeError::new("Not yet implemented".to_string()))
         }
     }
+
+    pub fn compute_weighted(&self, inputs: &Vec<Box<f64>>, weights: &[f64]) -> Result<f64, ComputeError> {
+        if let Self::WeightedAvg(_) = self {} else {
+            return self.compute(inputs);
+        }
+        if inputs.len() != weights.len() {
+            return Err(ComputeError::new("Number of weights does not match number of values".to_string()));
+        }
+        let total_weight = FSum::new().add_all(weights.iter()).value();
+        if inputs.is_empty() || total_weight == 0.0 {
+            return Err(ComputeError::new(String::new()));
+        }
+
+        let weighted_values = inputs.iter()
+            .zip(weights.iter())
+            .map(|(v, w)| v.as_ref() * w);
+
+        Ok(FSum::new().add_all(weighted_values).value() / total_weight)
+    }
 }
 
 #[derive(Debug)]
@@ -150,12 +195,59 @@ impl IndicatorInput {
     }
 
     pub fn get_value(&self) -> Option<f64> {
-        if let Some(_) = self.input.borrow().inputed {
-            return self.input.borrow().inputed;
+        if let Some(_) = self.input.borrow().inputed() {
+            return self.input.borrow().inputed();
         }
         if let Some(_) = self.input.borrow().computed {
             return self.input.borrow().computed;
         }
         None
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_avg() {
+        let mode = ComputerMode::WeightedAvg(Weighting::DaysInMonth);
+        let values = vec![Box::new(10.0), Box::new(20.0)];
+        let x = mode.compute_weighted(&values, &[1.0, 3.0]).unwrap();
+        assert_eq!(x, 17.5);
+    }
+
+    #[test]
+    fn weighted_avg_without_weights() {
+        let mode = ComputerMode::WeightedAvg(Weighting::Indicator(&SALES_CODE));
+        let values = vec![Box::new(10.0), Box::new(20.0)];
+        assert!(mode.compute(&values).is_err());
+        let e = mode.compute_weighted(&values, &[0.0, 0.0]).err().unwrap();
+        assert_eq!(e.message().len(), 0);
+        let e = mode.compute_weighted(&values, &[1.0]).err().unwrap();
+        assert!(e.message().len() > 0);
+    }
+
+    #[test]
+    fn first_min_max() {
+        let values = vec![Box::new(4.0), Box::new(-2.5), Box::new(9.0), Box::new(1.0)];
+        assert_eq!(ComputerMode::First.compute(&values).unwrap(), 4.0);
+        assert_eq!(ComputerMode::Default.compute(&values).unwrap(), 1.0);
+        assert_eq!(ComputerMode::Min.compute(&values).unwrap(), -2.5);
+        assert_eq!(ComputerMode::Max.compute(&values).unwrap(), 9.0);
+    }
+
+    #[test]
+    fn median() {
+        let mut values = vec![Box::new(4.0), Box::new(-2.5), Box::new(9.0)];
+        assert_eq!(ComputerMode::Median.compute(&values).unwrap(), 4.0);
+        values.push(Box::new(1.0));
+        assert_eq!(ComputerMode::Median.compute(&values).unwrap(), 2.5);
+        assert!(ComputerMode::Median.compute(&vec![]).is_err());
+    }
+
+    #[test]
+    fn weighted_fallback() {
+        let values = vec![Box::new(10.0), Box::new(20.0)];
+        assert_eq!(ComputerMode::AddUp.compute_weighted(&values, &[]).unwrap(), 30.0);
+    }
+}
@@ -29,16 +29,20 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn start_compute() -> Result<(), Box<dyn Error>> {
     let monitor = InputMonitoring::build(
         InputContext::build(1), 
-        data::load_context(1));
+        data::load_context(1)?);
     
     let mut inputs = data::get_all_inputs();
 
+    for w in monitor.validate(&inputs) {
+        println!("Warning: {}", w);
+    }
+
     let inputs = monitor.compute(&mut inputs)?;
 
     println!();
     for i in inputs {
         let value: String;
-        match i.input.borrow().inputed {
+        match i.input.borrow().inputed() {
             Some(f) => value = f.to_string(),
             None => match i.input.borrow().computed {
                 Some(f) => value = f.to_string(),
@@ -60,5 +64,11 @@ fn start_compute() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    let report = monitor.check_covenants(inputs);
+    println!();
+    for t in report.breaches() {
+        println!("Breach: {}", t);
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,205 @@
+use std::{ops::Range, rc::Rc};
+
+use super::{bk_error::{BracketsError, ErrorCode}, BracketSection, Brackets};
+
+pub const OVERLAPPING_PATCHES: &str = "Patches overlap";
+
+// lossless view of a document read from text: nodes only hold positions in the source, what lies
+// between them (spaces, line breaks, comments) is left untouched by the edits
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstNode {
+    // trimmed name, free name delimiters included: [@ name @]
+    pub name: Option<Range<usize>>,
+    // opening token, e.g. [, [|, [, or [=int{
+    pub open: Range<usize>,
+    // trimmed single value
+    pub value: Option<Range<usize>>,
+    // closing token, e.g. ], |], ,] or }]
+    pub close: Range<usize>,
+    pub children: Vec<CstNode>
+}
+
+// replaces the bytes of range by text in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub range: Range<usize>,
+    pub text: String
+}
+
+impl CstNode {
+    pub fn span(&self) -> Range<usize> {
+        self.name.as_ref().map_or(self.open.start, |n| n.start)..self.close.end
+    }
+
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span()]
+    }
+
+    // spaces from the start of the line to the node, empty when other text comes first
+    pub fn indent<'a>(&self, source: &'a str) -> &'a str {
+        let start = self.span().start;
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let prefix = &source[line_start..start];
+        if prefix.trim().is_empty() { prefix } else { "" }
+    }
+}
+
+impl Patch {
+    pub fn new(range: Range<usize>, text: String) -> Patch {
+        Patch { range, text }
+    }
+
+    // None when two patches overlap, insertions at the same position are kept in order
+    pub fn apply(source: &str, mut patches: Vec<Patch>) -> Option<String> {
+        patches.sort_by_key(|p| (p.range.start, p.range.end));
+        if patches.windows(2).any(|w| w[1].range.start < w[0].range.end) {
+            return None;
+        }
+        let mut text = String::with_capacity(source.len());
+        let mut position = 0;
+        for patch in patches.iter() {
+            text.push_str(source.get(position..patch.range.start)?);
+            text.push_str(&patch.text);
+            position = patch.range.end;
+        }
+        text.push_str(source.get(position..)?);
+        Some(text)
+    }
+}
+
+impl Brackets {
+    // tree of the document positions, None when it was not read from text, e.g. built from json
+    pub fn cst(&self) -> Option<CstNode> {
+        self.cst_node(&self.root.borrow())
+    }
+
+    // applies the patches to the source then parses it again
+    pub fn patch(&mut self, patches: Vec<Patch>) -> Result<(), BracketsError> {
+        self.can_search()?;
+        match Patch::apply(self.get_buffer(), patches) {
+            Some(text) => self.rebuild(text),
+            None => Err(BracketsError::coded(ErrorCode::Edit, OVERLAPPING_PATCHES.to_owned()))
+        }
+    }
+
+    pub(crate) fn cst_node(&self, section: &BracketSection) -> Option<CstNode> {
+        let BracketSection::Array(a) = section else { return None };
+        let is_root = matches!(&*self.root.borrow(), BracketSection::Array(r) if Rc::ptr_eq(r, a));
+        let a = a.borrow();
+        let idx = if is_root { self.get_start_index() } else { a.name.end + 1 };
+        let open = &self.open_bks[*self.open_bks_hash.get(&idx)?];
+        let close = &self.close_bks[*self.close_bks_hash.get(&open.linked_idx)?];
+        if open.idx != idx || close.linked_idx != idx {
+            return None;
+        }
+        let buffer = self.get_buffer();
+        let name = match buffer.get(a.name.start..=a.name.end) {
+            Some(n) if !is_root && !a.name.is_empty && !n.trim().is_empty() => {
+                let start = a.name.start + n.len() - n.trim_start().len();
+                Some(start..start + n.trim().len())
+            },
+            _ => None
+        };
+        let mut node = CstNode {
+            name,
+            open: idx..open.get_inside_value_index(),
+            value: None,
+            close: close.idx..close.get_outside_value_index(),
+            children: vec![]
+        };
+        for child in a.array.iter() {
+            match &**child {
+                BracketSection::Array(_) => node.children.push(self.cst_node(child)?),
+                BracketSection::Str(v) | BracketSection::Int(v) | BracketSection::Real(v) if a.array.len() == 1 => {
+                    let v = v.borrow();
+                    let text = buffer.get(node.open.end..node.close.start)?;
+                    let start = node.open.end + text.len() - text.trim_start().len();
+                    node.value = Some(start..start + text.trim().len());
+                    if v.is_empty {
+                        node.value = None;
+                    }
+                },
+                BracketSection::NoVal => {},
+                _ => return None
+            }
+        }
+        Some(node)
+    }
+
+    // new source once the edit applied, the whole document is written again when it has no
+    // source positions or when the patches cannot be applied
+    pub(crate) fn apply_edit(&mut self, patches: Option<Vec<Patch>>) -> Result<(), BracketsError> {
+        match patches.and_then(|p| Patch::apply(self.get_buffer(), p)) {
+            Some(text) => self.rebuild(text),
+            None => self.reindex()
+        }
+    }
+
+    pub(crate) fn rebuild(&mut self, text: String) -> Result<(), BracketsError> {
+        let mut new = Brackets::build_with_config(text, self.config.clone())?;
+        new.file_name = self.file_name.take();
+        *self = new;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "**[ countries ]**\n[\n    a[=int{12}]\n    items[\n        [x]\n        [ y ]\n        **[ last ]**\n    ]\n    l[,x,y,]\n    f[|t[1]|]\n    [@ n[1] @][v]\n]\n";
+
+    #[test]
+    fn read_nodes() {
+        let bk = Brackets::build_from_string(TEXT.to_owned()).unwrap();
+        let root = bk.cst().unwrap();
+        assert_eq!(root.name, None);
+        assert_eq!(root.text(TEXT), &TEXT[18..TEXT.len() - 1]);
+        let texts: Vec<&str> = root.children.iter().map(|c| c.text(TEXT)).collect();
+        assert_eq!(texts, vec!["a[=int{12}]", "items[\n        [x]\n        [ y ]\n        **[ last ]**\n    ]", "l[,x,y,]", "f[|t[1]|]", "[@ n[1] @][v]"]);
+        let int = &root.children[0];
+        assert_eq!((&TEXT[int.open.clone()], &TEXT[int.value.clone().unwrap()], &TEXT[int.close.clone()]), ("[=int{", "12", "}]"));
+        assert_eq!(&TEXT[root.children[1].children[1].value.clone().unwrap()], "y");
+        assert_eq!(root.children[1].children[1].indent(TEXT), "        ");
+        assert_eq!(&TEXT[root.children[2].value.clone().unwrap()], ",x,y");
+        assert_eq!(&TEXT[root.children[3].close.clone()], "|]");
+        assert_eq!(&TEXT[root.children[4].name.clone().unwrap()], "[@ n[1] @]");
+        assert!(Brackets::build_from_json("{\"a\": 1}").unwrap().cst().is_none());
+    }
+
+    #[test]
+    fn apply_patches() {
+        let mut bk = Brackets::build_from_string(TEXT.to_owned()).unwrap();
+        let root = bk.cst().unwrap();
+        let value = root.children[0].value.clone().unwrap();
+        let name = root.children[2].name.clone().unwrap();
+        bk.patch(vec![Patch::new(value, String::from("13")), Patch::new(name, String::from("list"))]).unwrap();
+        assert_eq!(bk.get_buffer(), TEXT.replace("12", "13").replace("l[", "list["));
+        assert_eq!(bk.read::<i32>("a").unwrap(), 13);
+        assert!(bk.patch(vec![Patch::new(2..6, String::new()), Patch::new(4..8, String::new())]).is_err());
+    }
+
+    #[test]
+    fn keep_layout() {
+        let source = include_str!("../../data/db.bk");
+        let mut bk = Brackets::build_from_string(source.to_owned()).unwrap();
+        bk.set("data.countries[0].code", &String::from("CHE")).unwrap();
+        assert_eq!(bk.get_buffer(), source.replacen("code[CH]", "code[CHE]", 1));
+        bk.rename("data.countries[1].currencyCode", "currency").unwrap();
+        bk.remove("data.divisions[code=10]").unwrap();
+        bk.insert("data.countries[1]", "rank", &2).unwrap();
+        bk.append_item("model.currency", &String::from("rate")).unwrap();
+        let text = bk.get_buffer().to_owned();
+        assert!(text.starts_with("@[\n    name[trees]\n"));
+        assert!(text.contains("                currency[USD]\n                rank[=int{2}]\n            ]"));
+        assert!(text.contains("currency[,code<3>,symbol,label,rate,]"));
+        assert!(!text.contains("absolute block 10"));
+        assert_eq!(text.lines().count(), source.lines().count() - 5 + 1);
+        assert_eq!(bk.find_values("data.divisions[*].code").unwrap(), vec!["45", "1"]);
+
+        // untouched lines are kept as they were
+        let edited: Vec<&str> = text.lines().collect();
+        let kept = source.lines().filter(|l| edited.contains(l)).count();
+        assert_eq!(kept, source.lines().count() - 6);
+    }
+}
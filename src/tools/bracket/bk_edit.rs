@@ -0,0 +1,310 @@
+use std::rc::Rc;
+
+use super::{bk_cst::{CstNode, Patch}, bk_error::{BracketsError, ErrorCode}, bk_mapping::{array_section, named_section, ToBrackets}, bk_pretty::PrettyConfig, BracketSection, BracketType, BracketValue, Brackets, CLOSE, COMMA_CHAR, OPEN};
+
+pub const PATH_NOT_FOUND: &str = "No section found for";
+pub const NOT_EDITABLE: &str = "Section cannot be edited";
+pub const NOT_A_LIST: &str = "Expected a list or an array";
+pub const INVALID_ITEM: &str = "Invalid list item";
+
+// edits work on the tree, then only the touched values are rewritten in the source which is parsed
+// back, so the bracket positions stay consistent and the layout and comments are kept. Documents
+// without source, e.g. built from json, are written again. Paths are the ones of find, an empty
+// path being the root. Each edit returns the number of sections changed
+impl Brackets {
+    // adds a named child to the arrays matching the parent path, absent values are left out
+    pub fn insert<T: ToBrackets>(&mut self, parent: &str, name: &str, value: &T) -> Result<usize, BracketsError> {
+        let targets = self.edit_targets(parent)?;
+        let mut patches = Some(vec![]);
+        for target in targets.iter() {
+            let BracketSection::Array(a) = target else { return Err(Self::not_editable(parent)) };
+            if let Some(child) = named_section(name, value.to_brackets()) {
+                Self::add_patch(&mut patches, self.insert_patch(target, &child));
+                a.borrow_mut().array.push(Rc::new(child));
+            }
+        }
+        self.apply_edit(patches)?;
+        Ok(targets.len())
+    }
+
+    // replaces the value of the matching sections, their names are kept
+    pub fn replace<T: ToBrackets>(&mut self, path: &str, value: &T) -> Result<usize, BracketsError> {
+        let targets = self.edit_targets(path)?;
+        let mut patches = Some(vec![]);
+        for target in targets.iter() {
+            let BracketSection::Array(a) = target else { return Err(Self::not_editable(path)) };
+            let node = self.cst_node(target);
+            a.borrow_mut().array = match value.to_brackets() {
+                BracketSection::Array(v) => v.take().array,
+                v => vec![Rc::new(v)]
+            };
+            Self::add_patch(&mut patches, node.map(|n| self.value_patch(&n, target)));
+        }
+        self.apply_edit(patches)?;
+        Ok(targets.len())
+    }
+
+    // replaces the value at the path or inserts it when missing, the last step being its name:
+    // set("data.count", &12) writes count[=int{12}] into data
+    pub fn set<T: ToBrackets>(&mut self, path: &str, value: &T) -> Result<usize, BracketsError> {
+        if !self.find(path)?.is_empty() {
+            return self.replace(path, value);
+        }
+        let (parent, name) = Self::split_last_name(path)?;
+        self.insert(&parent, &name, value)
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<usize, BracketsError> {
+        let targets = self.edit_targets(path)?;
+        if targets.iter().any(|t| Self::is_same(t, &self.root.borrow())) {
+            return Err(Self::not_editable(path));
+        }
+        let mut patches = Some(vec![]);
+        for target in targets.iter() {
+            Self::add_patch(&mut patches, self.cst_node(target).map(|n| self.remove_patch(&n)));
+        }
+        Self::remove_sections(&self.root.borrow(), &targets);
+        self.apply_edit(patches)?;
+        Ok(targets.len())
+    }
+
+    pub fn rename(&mut self, path: &str, name: &str) -> Result<usize, BracketsError> {
+        let targets = self.edit_targets(path)?;
+        let mut patches = Some(vec![]);
+        for target in targets.iter() {
+            match target {
+                BracketSection::Array(a) if !Self::is_same(target, &self.root.borrow()) => {
+                    let range = self.cst_node(target).map(|n| n.name.unwrap_or(n.open.start..n.open.start));
+                    Self::add_patch(&mut patches, range.map(|r| Patch::new(r, Self::name_to_text(name))));
+                    a.borrow_mut().name = BracketValue { btyp: BracketType::Name, ..BracketValue::new_value(name) };
+                },
+                _ => return Err(Self::not_editable(path))
+            }
+        }
+        self.apply_edit(patches)?;
+        Ok(targets.len())
+    }
+
+    // adds an item to a list value, [,a,b,], or to an array of items, an empty value becomes a list
+    pub fn append_item<T: ToBrackets>(&mut self, path: &str, item: &T) -> Result<usize, BracketsError> {
+        let targets = self.edit_targets(path)?;
+        let mut patches = Some(vec![]);
+        for target in targets.iter() {
+            let BracketSection::Array(a) = target else { return Err(Self::not_editable(path)) };
+            let children = Self::children(target);
+            let list = match children.as_slice() {
+                [] | [BracketSection::NoVal] => Some(String::new()),
+                [BracketSection::Str(v)] => {
+                    let text = self.get_text(&v.borrow());
+                    if text.starts_with(COMMA_CHAR) || text.is_empty() { Some(text) }
+                    else { return Err(BracketsError::coded(ErrorCode::Edit, format!("{} '{}'", NOT_A_LIST, path))) }
+                },
+                c if c.iter().all(|s| matches!(s, BracketSection::Array(_))) => None,
+                _ => return Err(BracketsError::coded(ErrorCode::Edit, format!("{} '{}'", NOT_A_LIST, path)))
+            };
+            match list {
+                Some(text) => {
+                    let value = self.get_value_text(&item.to_brackets()).unwrap_or_default();
+                    if value.contains(COMMA_CHAR) || value.contains(OPEN) || value.contains(CLOSE) {
+                        return Err(BracketsError::coded(ErrorCode::Edit, format!("{} '{}'", INVALID_ITEM, value)));
+                    }
+                    let node = self.cst_node(target);
+                    a.borrow_mut().array = vec![Rc::new(format!("{}{}{}", text, COMMA_CHAR, value).to_brackets())];
+                    Self::add_patch(&mut patches, node.map(|n| self.value_patch(&n, target)));
+                },
+                None => {
+                    for child in Self::children(&array_section(vec![item.to_brackets()])) {
+                        Self::add_patch(&mut patches, self.insert_patch(target, &child));
+                        a.borrow_mut().array.push(Rc::new(child));
+                    }
+                }
+            }
+        }
+        self.apply_edit(patches)?;
+        Ok(targets.len())
+    }
+
+    // writes the document and parses it back
+    pub fn reindex(&mut self) -> Result<(), BracketsError> {
+        self.can_search()?;
+        self.rebuild(self.to_bk_string())
+    }
+
+    fn edit_targets(&self, path: &str) -> Result<Vec<BracketSection>, BracketsError> {
+        let targets = if path.trim().is_empty() {
+            self.can_search()?;
+            vec![self.root.borrow().clone()]
+        }
+        else {
+            self.find(path)?
+        };
+        if targets.is_empty() {
+            return Err(BracketsError::coded(ErrorCode::Edit, format!("{} '{}'", PATH_NOT_FOUND, path)));
+        }
+        Ok(targets)
+    }
+
+    fn not_editable(path: &str) -> BracketsError {
+        BracketsError::coded(ErrorCode::Edit, format!("{} '{}'", NOT_EDITABLE, path))
+    }
+
+    fn is_same(a: &BracketSection, b: &BracketSection) -> bool {
+        match (a, b) {
+            (BracketSection::Array(x), BracketSection::Array(y)) => Rc::ptr_eq(x, y),
+            (BracketSection::Str(x), BracketSection::Str(y))
+            | (BracketSection::Int(x), BracketSection::Int(y))
+            | (BracketSection::Real(x), BracketSection::Real(y)) => Rc::ptr_eq(x, y),
+            _ => false
+        }
+    }
+
+    fn remove_sections(section: &BracketSection, targets: &[BracketSection]) {
+        if let BracketSection::Array(a) = section {
+            a.borrow_mut().array.retain(|c| !targets.iter().any(|t| Self::is_same(c, t)));
+            for child in a.borrow().array.iter() {
+                Self::remove_sections(child, targets);
+            }
+        }
+    }
+
+    fn add_patch(patches: &mut Option<Vec<Patch>>, patch: Option<Patch>) {
+        match (patches.as_mut(), patch) {
+            (Some(p), Some(patch)) => p.push(patch),
+            _ => *patches = None
+        }
+    }
+
+    // the brackets of an edited section written in place of the old ones
+    fn value_patch(&self, node: &CstNode, section: &BracketSection) -> Patch {
+        let indent = node.indent(self.get_buffer());
+        Patch::new(node.open.start..node.close.end, self.brackets_to_text(section, indent, &PrettyConfig::default()))
+    }
+
+    // a new child on its own line after the last child of the parent
+    fn insert_patch(&self, parent: &BracketSection, child: &BracketSection) -> Option<Patch> {
+        let node = self.cst_node(parent)?;
+        if node.value.is_some() {
+            return None;
+        }
+        let buffer = self.get_buffer();
+        let pretty = PrettyConfig::default();
+        match node.children.last() {
+            Some(last) => {
+                let indent = last.indent(buffer);
+                let end = last.span().end;
+                Some(Patch::new(end..end, format!("\n{}{}", indent, self.section_to_text(child, indent, &pretty))))
+            },
+            None => {
+                let indent = node.indent(buffer);
+                let inner = format!("{}{}", indent, pretty.indent_unit());
+                let start = node.close.start;
+                Some(Patch::new(start..start, format!("\n{}{}\n{}", inner, self.section_to_text(child, &inner, &pretty), indent)))
+            }
+        }
+    }
+
+    // the line of the section goes with it when nothing else comes before on the line
+    fn remove_patch(&self, node: &CstNode) -> Patch {
+        let buffer = self.get_buffer();
+        let span = node.span();
+        let line_start = buffer[..span.start].rfind('\n');
+        match line_start {
+            Some(l) if buffer[l..span.start].trim().is_empty() => Patch::new(l..span.end, String::new()),
+            _ => Patch::new(span, String::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::DayDate;
+
+    fn load_db() -> Brackets {
+        Brackets::build_from_string(include_str!("../../data/db.bk").to_owned()).unwrap()
+    }
+
+    #[test]
+    fn edit_values() {
+        let mut bk = load_db();
+        assert_eq!(bk.set("data.countries[0].code", &String::from("CHE")).unwrap(), 1);
+        assert_eq!(bk.insert("data.countries[1]", "rank", &2).unwrap(), 1);
+        assert_eq!(bk.set("data.updated", &DayDate::new(2024, 5, 15)).unwrap(), 1);
+        assert_eq!(bk.rename("data.countries[*].currencyCode", "currency").unwrap(), 2);
+        assert_eq!(bk.remove("data.divisions[code=10]").unwrap(), 1);
+        assert_eq!(bk.append_item("model.currency", &String::from("rate")).unwrap(), 1);
+
+        assert_eq!(bk.find_values("data.countries[*].code").unwrap(), vec!["CHE", "USA"]);
+        assert_eq!(bk.read::<i64>("data.countries[1].rank").unwrap(), 2);
+        assert!(matches!(Brackets::children(&bk.find("data.countries[1].rank").unwrap()[0])[0], BracketSection::Int(_)));
+        assert_eq!(bk.read::<DayDate>("data.updated").unwrap(), DayDate::new(2024, 5, 15));
+        assert_eq!(bk.find_values("data..currency").unwrap(), vec!["CFR", "USD"]);
+        assert!(bk.find("data..currencyCode").unwrap().is_empty());
+        assert_eq!(bk.find_values("data.divisions[*].code").unwrap(), vec!["45", "1"]);
+        assert_eq!(bk.find_values("model.currency").unwrap(), vec![",code<3>,symbol,label,rate"]);
+        assert_eq!(bk.find_values("data.\"[dbo].[other]\"").unwrap(), vec!["test \\[\\]"]);
+        assert_eq!(bk.config.name, "trees");
+
+        // the index matches the one of the edited text parsed again
+        let text = bk.get_buffer().to_owned();
+        assert_eq!(bk.get_nb_chunks(), Brackets::build_from_string(text).unwrap().get_nb_chunks());
+        let offset = bk.get_offset(&bk.find("data.countries[0].code").unwrap()[0]);
+        assert_eq!(&bk.get_buffer()[offset..offset + 9], "code[CHE]");
+    }
+
+    #[test]
+    fn edit_items() {
+        let mut bk = Brackets::build_from_string("[\n    items[\n        [a]\n    ]\n    list[,x,]\n]\n".to_owned()).unwrap();
+        assert_eq!(bk.append_item("items", &String::from("b")).unwrap(), 1);
+        assert_eq!(bk.find_values("items[*]").unwrap(), vec!["a", "b"]);
+        bk.append_item("list", &String::from("y")).unwrap();
+        assert_eq!(bk.find_values("list").unwrap(), vec![",x,y"]);
+        assert!(bk.append_item("list", &String::from("a,b")).is_err());
+        bk.set("items[0]", &String::from("free [text]")).unwrap();
+        bk.insert("", "name [1]", &String::from("|piped|]")).unwrap();
+        assert_eq!(bk.find_values("items[0]").unwrap(), vec!["free [text]"]);
+        assert_eq!(bk.find_values("\"name [1]\"").unwrap(), vec!["|piped|]"]);
+    }
+
+    #[test]
+    fn set_dotted_names() {
+        let mut bk = Brackets::build_from_string("[\n    [@ a.b @][1]\n    items[\n        [\n            code[1.5]\n        ]\n    ]\n]\n".to_owned()).unwrap();
+        assert_eq!(bk.set("\"a.b\"", &2).unwrap(), 1);
+        assert_eq!(bk.set("\"c.d\"", &3).unwrap(), 1);
+        assert_eq!(bk.set("items[code=1.5].label", &String::from("x")).unwrap(), 1);
+        assert_eq!(bk.set("items[code=1.5].\"u.v\"", &String::from("y")).unwrap(), 1);
+        assert_eq!(bk.find_values("\"a.b\"").unwrap(), vec!["2"]);
+        assert_eq!(bk.find_values("\"c.d\"").unwrap(), vec!["3"]);
+        assert_eq!(bk.find_values("items[0].label").unwrap(), vec!["x"]);
+        assert_eq!(bk.find_values("items[0].\"u.v\"").unwrap(), vec!["y"]);
+        assert!(bk.set("items[code=2.5]", &1).is_err());
+        assert!(bk.set("..code2", &1).is_err());
+    }
+
+    #[test]
+    fn edit_errors() {
+        let mut bk = load_db();
+        let error = bk.remove("data.unknown").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::Edit);
+        assert!(error.to_string().starts_with(PATH_NOT_FOUND));
+        assert!(bk.append_item("data.countries[0].code", &String::from("x")).is_err());
+        assert!(bk.rename("", "root").is_err());
+        assert!(bk.remove("data[").is_err());
+        assert_eq!(bk.find_values("data.countries[0].code").unwrap(), vec!["CH"]);
+    }
+
+    #[test]
+    fn write_json_document() {
+        let bk = Brackets::build_from_json(include_str!("../../data/products.json")).unwrap();
+        let written = Brackets::build_from_string(bk.to_bk_string()).unwrap();
+        assert_eq!(written.to_json().unwrap(), bk.to_json().unwrap());
+        let bk = load_db();
+        let written = Brackets::build_from_string(bk.to_bk_string()).unwrap();
+        assert_eq!(written.to_json().unwrap(), bk.to_json().unwrap());
+
+        // without source the document is written again
+        let mut bk = Brackets::build_from_json("{\"a\": {\"b\": 1}}").unwrap();
+        bk.set("a.c", &2.5).unwrap();
+        assert_eq!(bk.get_buffer(), "[\n    a[\n        b[=int{1}]\n        c[=real{2.5}]\n    ]\n]\n");
+    }
+}
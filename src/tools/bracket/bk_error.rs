@@ -2,25 +2,116 @@ use std::{error::Error, fmt::{Display, Formatter}};
 
 use super::{BracketChunk, BracketType};
 
-pub const UNKNOWN_ERROR: &str = "Unknown brackets error";
-pub const INVALID_CONFIG: &str = "Invalid configuration";
-pub const EMPTY_STRING: &str = "Empty string";
-pub const BASE_FORMAT_ERROR: &str = "Start and/or end of the text is malformed";
-pub const FORMAT_ERROR: &str = "Input string is malformed";
-pub const BAD_ENDING: &str = "No opening bracket found for ";
-pub const BAD_START_COMMENT: &str = "Missing start of comment";
-pub const BAD_ENDING_COMMENT: &str = "Missing end of comment";
-
 pub const WARNING_EMPTY_FREE_TEXT: usize = 1;
 pub const WARNING_ESCAPED: usize = 2;
 pub const WARNING_FREE_TEXT: usize = 3;
 pub const WARNING_MASK: usize = 4;
 pub const WARNING_FREE_NAME: usize = 5;
+pub const WARNING_EMPTY_CONFIG: usize = 6;
+
+// names used by the strict config to promote warnings to errors, e.g. strict[escaped free_name]
+const WARNING_NAMES: [(&str, usize); 6] = [
+    ("empty_free_text", WARNING_EMPTY_FREE_TEXT),
+    ("escaped", WARNING_ESCAPED),
+    ("free_text", WARNING_FREE_TEXT),
+    ("mask", WARNING_MASK),
+    ("free_name", WARNING_FREE_NAME),
+    ("empty_config", WARNING_EMPTY_CONFIG)
+];
+pub const STRICT_ALL: &str = "all";
+
+pub fn warning_from_name(name: &str) -> Option<usize> {
+    WARNING_NAMES.iter().find(|(n, _)| *n == name).map(|(_, c)| *c)
+}
+
+pub fn warning_explanation(code: usize) -> &'static str {
+    match code {
+        WARNING_EMPTY_FREE_TEXT => "Empty free text",
+        WARNING_ESCAPED => "Escaped bracket read as text",
+        WARNING_FREE_TEXT => "Bracket inside a free text read as text",
+        WARNING_MASK => "Free text delimiter may hide a closing bracket",
+        WARNING_FREE_NAME => "Free name, brackets inside it are read as text",
+        WARNING_EMPTY_CONFIG => "Empty config value, the given one is kept",
+        _ => "Unknown warning"
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Unknown,
+    InvalidConfig,
+    EmptyString,
+    BaseFormat,
+    Format,
+    BadEnding,
+    Unclosed,
+    BadStartComment,
+    BadEndingComment,
+    Io,
+    Json,
+    Query,
+    Mapping,
+    Schema,
+    Edit,
+    Warning(usize)
+}
+
+impl ErrorCode {
+    pub fn message(&self) -> &'static str {
+        match self {
+            ErrorCode::Unknown => "Unknown brackets error",
+            ErrorCode::InvalidConfig => "Invalid configuration",
+            ErrorCode::EmptyString => "Empty string",
+            ErrorCode::BaseFormat => "Start and/or end of the text is malformed",
+            ErrorCode::Format => "Input string is malformed",
+            ErrorCode::BadEnding => "No opening bracket found",
+            ErrorCode::Unclosed => "No closing bracket found",
+            ErrorCode::BadStartComment => "Missing start of comment",
+            ErrorCode::BadEndingComment => "Missing end of comment",
+            ErrorCode::Io => "Unable to read the source",
+            ErrorCode::Json => "Unable to read json",
+            ErrorCode::Query => "Invalid search",
+            ErrorCode::Mapping => "Unable to map the value",
+            ErrorCode::Schema => "Invalid model",
+            ErrorCode::Edit => "Unable to edit the document",
+            ErrorCode::Warning(code) => warning_explanation(*code)
+        }
+    }
+}
+
+// line and column start at 1, the snippet is the offending line with a caret under the column
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String
+}
+
+impl Location {
+    pub fn find(buffer: &str, offset: usize) -> Location {
+        let offset = (0..=offset.min(buffer.len())).rev().find(|i| buffer.is_char_boundary(*i)).unwrap_or(0);
+        let line_start = buffer[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = buffer[..offset].matches('\n').count() + 1;
+        let prefix = &buffer[line_start..offset];
+        let text = buffer[line_start..].split('\n').next().unwrap_or_default().trim_end_matches('\r');
+        let padding: String = prefix.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let width = line.to_string().len();
+        Location {
+            line,
+            column: prefix.chars().count() + 1,
+            snippet: format!("{:>w$} | {}\n{:>w$} | {}^", line, text, "", padding, w = width)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct BracketsError {
     message: String,
-    warning: String
+    warning: String,
+    code: ErrorCode,
+    offset: Option<usize>,
+    location: Option<Box<Location>>,
+    others: Vec<BracketsError>
 }
 
 impl BracketsError {
@@ -29,11 +120,23 @@ impl BracketsError {
     }
 
     pub fn error(message: String) -> BracketsError {
-        BracketsError { message, warning: Default::default() }
+        Self::coded(ErrorCode::Unknown, message)
     }
 
     pub fn warning(message: String) -> BracketsError {
-        BracketsError { message: Default::default(), warning: message }
+        BracketsError { message: Default::default(), warning: message, ..Self::from_code(ErrorCode::Unknown) }
+    }
+
+    pub fn from_warning(code: usize) -> BracketsError {
+        Self::warning(warning_explanation(code).to_owned()).with_code(ErrorCode::Warning(code))
+    }
+
+    pub fn from_code(code: ErrorCode) -> BracketsError {
+        Self::coded(code, code.message().to_owned())
+    }
+
+    pub fn coded(code: ErrorCode, message: String) -> BracketsError {
+        BracketsError { message, warning: Default::default(), code, offset: None, location: None, others: vec![] }
     }
 
     pub fn error_close(cbk: &BracketChunk) -> BracketsError {
@@ -42,16 +145,72 @@ impl BracketsError {
             BracketType::List => "List ",
             _ => ""
         };
-        
-        let msg = format!("{}{}closing at {}", BAD_ENDING, typ, cbk.idx);
-        
-        BracketsError::error(msg)
+
+        let msg = format!("{} for {}closing", ErrorCode::BadEnding.message(), typ);
+
+        BracketsError::coded(ErrorCode::BadEnding, msg).at(cbk.idx)
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> BracketsError {
+        self.code = code;
+        self
+    }
+
+    // position in the source buffer the error refers to
+    pub fn at(mut self, offset: usize) -> BracketsError {
+        self.offset = Some(offset);
+        self
+    }
+
+    // computes line, column and snippet of this error and of the other diagnostics
+    pub fn locate(mut self, buffer: &str) -> BracketsError {
+        if let Some(offset) = self.offset {
+            self.location = Some(Box::new(Location::find(buffer, offset)));
+        }
+        self.others = self.others.into_iter().map(|e| e.locate(buffer)).collect();
+        self
+    }
+
+    pub fn with_diagnostics(mut self, others: Vec<BracketsError>) -> BracketsError {
+        self.others.extend(others);
+        self
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.message.is_empty() && !self.warning.is_empty()
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_deref()
+    }
+
+    // this error followed by the other issues found during the same parse
+    pub fn diagnostics(&self) -> Vec<&BracketsError> {
+        let mut all = vec![self];
+        all.extend(self.others.iter());
+        all
     }
 
     pub(crate) fn message(&self) -> &String {
         if &self.message.len() > &0 { &self.message }
         else { &self.warning }
     }
+
+    fn fmt_single(&self, f: &mut Formatter) -> std::fmt::Result {
+        match (&self.location, self.offset) {
+            (Some(l), _) => write!(f, "{} at line {}, column {}\n{}", self.message(), l.line, l.column, l.snippet),
+            (None, Some(offset)) => write!(f, "{} at {}", self.message(), offset),
+            _ => write!(f, "{}", self.message())
+        }
+    }
 }
 
 impl Error for BracketsError {
@@ -62,12 +221,35 @@ impl Error for BracketsError {
 
 impl Default for BracketsError {
     fn default() -> Self {
-        Self::error(UNKNOWN_ERROR.to_owned())
+        Self::from_code(ErrorCode::Unknown)
     }
 }
 
 impl Display for BracketsError {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        write!(f,"{}", self.message())
+        self.fmt_single(f)?;
+        for other in self.others.iter() {
+            writeln!(f)?;
+            other.fmt_single(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let text = "[\n  name[a]\n\tcode[b]]\n]\n";
+        let location = Location::find(text, 12);
+        assert_eq!((location.line, location.column), (3, 1));
+        let location = Location::find(text, 19);
+        assert_eq!((location.line, location.column), (3, 8));
+        assert_eq!(location.snippet, "3 | \tcode[b]]\n  | \t      ^");
+        let error = BracketsError::from_code(ErrorCode::BadEnding).at(19).locate(text);
+        assert_eq!(error.to_string(), format!("No opening bracket found at line 3, column 8\n{}", location.snippet));
+        assert_eq!(BracketsError::from_code(ErrorCode::Format).at(19).to_string(), "Input string is malformed at 19");
     }
-}
\ No newline at end of file
+}
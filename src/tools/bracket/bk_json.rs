@@ -1,9 +1,10 @@
 use std::{cell::RefCell, rc::Rc};
 
 use date_format_parser::parse_date;
-use json::JsonValue;
+use json::{object::Object, JsonValue};
 
-use super::{bk_error::BracketsError, bk_regex::RGX_STD_INT, BkArray, BracketArray, BracketSection, BracketType, BracketValue, Brackets, CharSlice, CLOSE, COLON_CHAR, OPEN, PIPE_CHAR};
+use crate::date::{datetime::DateTime, DayDate};
+use super::{bk_error::{BracketsError, ErrorCode}, bk_regex::RGX_STD_INT, BkArray, BracketArray, BracketChunk, BracketSection, BracketType, BracketValue, Brackets, CharSlice, CLOSE, COLON_CHAR, OPEN, PIPE_CHAR};
 
 use lexical_parse_integer::FromLexical;
 
@@ -11,7 +12,7 @@ impl Brackets {
     pub fn build_from_json(json_string: &str) -> Result<Brackets, BracketsError> {
         let json_result = json::parse(json_string);
         if let Err(e) = json_result {
-            return Err(BracketsError::new(&format!("Unable to read json ({})", e)));
+            return Err(BracketsError::coded(ErrorCode::Json, format!("{} ({})", ErrorCode::Json.message(), e)));
         }
         let json = json_result.unwrap();
         Self::build_from_json_value(json)
@@ -85,6 +86,116 @@ impl Brackets {
         Ok(())
     }
 
+    // named arrays become objects (an array of single-key objects when names repeat),
+    // anonymous arrays become arrays, an array holding a single value becomes that value,
+    // Int and Real become numbers, Date becomes an ISO 8601 string, NoVal becomes null
+    // and any other value a string. Booleans read from json come back as strings and
+    // empty objects as empty arrays.
+    pub fn to_json(&self) -> Result<JsonValue, BracketsError> {
+        self.can_search()?;
+        Ok(self.section_to_json(&self.root.borrow()))
+    }
+
+    fn section_to_json(&self, section: &BracketSection) -> JsonValue {
+        match section {
+            BracketSection::NoVal => JsonValue::Null,
+            BracketSection::Int(v) | BracketSection::Real(v) => {
+                let text = self.get_text(&v.borrow());
+                match json::parse(&text) {
+                    Ok(n) if n.is_number() => n,
+                    _ => text.parse::<f64>().map_or(JsonValue::from(text), JsonValue::from)
+                }
+            },
+            BracketSection::Str(v) => {
+                let v = v.borrow();
+                let text = self.get_text(&v);
+                match v.btyp {
+                    BracketType::Date => JsonValue::from(Self::iso_date(text)),
+                    _ => JsonValue::from(text)
+                }
+            },
+            BracketSection::Array(array) => {
+                let a = array.borrow();
+                if let [single] = a.array.as_slice() {
+                    if !matches!(**single, BracketSection::Array(_)) {
+                        return self.section_to_json(single);
+                    }
+                }
+                // anonymous NoVal children are left out of objects
+                let named = self.child_names(array);
+                let (children, names): (Vec<&BracketSection>, Vec<Option<String>>) = a.array.iter()
+                    .map(|c| &**c)
+                    .zip(named.iter().cloned())
+                    .filter(|(c, name)| name.is_some() || !matches!(c, BracketSection::NoVal))
+                    .unzip();
+                let mut unique: Vec<&String> = names.iter().flatten().collect();
+                unique.sort();
+                unique.dedup();
+                if !children.is_empty() && unique.len() == children.len() {
+                    let mut object = Object::new();
+                    for (child, name) in children.iter().zip(names.iter().flatten()) {
+                        object.insert(name, self.section_to_json(child));
+                    }
+                    return JsonValue::Object(object);
+                }
+                JsonValue::Array(a.array.iter()
+                    .zip(named)
+                    .map(|(child, name)| match name {
+                        Some(name) => {
+                            let mut object = Object::new();
+                            object.insert(&name, self.section_to_json(child));
+                            JsonValue::Object(object)
+                        },
+                        None => self.section_to_json(child)
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    // names of the children, the tree only keeps a NoVal for c[] so its name is read again
+    // from the source
+    fn child_names(&self, array: &BkArray) -> Vec<Option<String>> {
+        let a = array.borrow();
+        let mut names: Vec<Option<String>> = a.array.iter().map(|c| self.named(c)).collect();
+        if !a.array.iter().any(|c| matches!(**c, BracketSection::NoVal)) {
+            return names;
+        }
+        let is_root = matches!(&*self.root.borrow(), BracketSection::Array(r) if Rc::ptr_eq(r, array));
+        let idx = if is_root { self.get_start_index() } else { a.name.end + 1 };
+        let Some(open) = self.open_bks_hash.get(&idx).map(|i| &self.open_bks[*i]) else { return names };
+        let mut children: Vec<&BracketChunk> = Vec::new();
+        for child in self.open_bks.iter().filter(|o| o.is_in_chunk(open)) {
+            if children.last().is_none_or(|last| !child.is_in_chunk(last)) {
+                children.push(child);
+            }
+        }
+        if open.idx != idx || children.len() != names.len() {
+            return names;
+        }
+        let mut key_start = open.get_inside_value_index();
+        for (i, child) in children.iter().enumerate() {
+            let Some(close) = self.close_bks_hash.get(&child.linked_idx).map(|c| &self.close_bks[*c]) else { return names };
+            if let BracketSection::NoVal = *a.array[i] {
+                let key = BracketArray { name: BracketValue::new(key_start, child.get_outside_value_index(), BracketType::Name), ..Default::default() };
+                names[i] = Some(self.get_name(&key)).filter(|n| !n.is_empty());
+            }
+            key_start = close.get_outside_value_index();
+        }
+        names
+    }
+
+    // ISO 8601 text is kept as written, other date formats are rewritten as yyyy-mm-dd
+    fn iso_date(text: String) -> String {
+        if DateTime::parse(&text).is_ok() {
+            return text;
+        }
+        match DayDate::parse(&text) {
+            Ok(d) => d.format("%Y-%m-%d").unwrap_or(text),
+            Err(_) => text
+        }
+    }
+
     fn get_basic_section_from_json(value: &JsonValue, bval: Option<BracketValue>, name: Option<&str>) -> Option<BracketSection> {
         let array = BkArray::new(RefCell::new(Default::default()));
         array.borrow_mut().name = BracketValue { btyp: BracketType::Name, value: Rc::new(RefCell::new(name.unwrap_or_default().to_string())), ..Default::default() };
@@ -350,4 +461,39 @@ mod tests_brackets_json {
             }
         }
     }
+
+    #[test]
+    fn to_json_round_trip() {
+        let text = include_str!("../../data/products.json");
+        let bk = Brackets::build_from_json(text).unwrap();
+        assert_eq!(bk.to_json().unwrap(), json::parse(text).unwrap());
+    }
+
+    #[test]
+    fn to_json_values() {
+        let text = r#"{ "id": 4, "price": 9.5, "at": "2024-05-23T08:56:21.618Z", "none": null, "tags": ["a", [1, 2], {}], "empty": [] }"#;
+        let bk = Brackets::build_from_json(text).unwrap();
+        let value = bk.to_json().unwrap();
+        assert!(value["id"].is_number());
+        assert_eq!(value["price"], 9.5);
+        assert_eq!(value["at"], "2024-05-23T08:56:21.618Z");
+        assert!(value["none"].is_null());
+        assert_eq!(value["tags"], json::array!["a", [1, 2], []]);
+        assert!(value["empty"].is_array());
+    }
+
+    #[test]
+    fn to_json_from_brackets() {
+        let text = "[\n  a[1]\n  b[ [x] [y[2]] ]\n  c[]\n  d[25/12/2024]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let value = bk.to_json().unwrap();
+        assert_eq!(value, json::object!{ "a": "1", "b": ["x", { "y": "2" }], "c": null, "d": "25/12/2024" });
+        let bk = Brackets::build_from_string("[\n  a[1]\n  a[2]\n]\n".to_owned()).unwrap();
+        assert_eq!(bk.to_json().unwrap(), json::array![{ "a": "1" }, { "a": "2" }]);
+        let bk = Brackets::build_from_string("[\n  a[]\n  a[2]\n  [@ e f @][]\n  []\n]\n".to_owned()).unwrap();
+        assert_eq!(bk.to_json().unwrap(), json::array![{ "a": null }, { "a": "2" }, { "e f": null }, null]);
+        let text = "[\n  d[=date{25/12/2024}]\n  n[=int{12}]\n  r[=real{1.5}]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        assert_eq!(bk.to_json().unwrap(), json::object!{ "d": "2024-12-25", "n": 12, "r": 1.5 });
+    }
 }
@@ -68,4 +68,40 @@ macro_rules! bk_config {
             )*
         }
     };
-}
\ No newline at end of file
+}
+
+// implements FromBrackets and ToBrackets for a struct, each field is read from the child
+// named like it or like the given key: bk_mapping!(Country { name, currency as "currencyCode" })
+#[macro_export]
+macro_rules! bk_mapping {
+    (@key $field:ident) => { stringify!($field) };
+    (@key $field:ident $key:literal) => { $key };
+    ($struct:ident { $($field:ident $(as $key:literal)?),* $(,)? }) => {
+        impl $crate::tools::bracket::bk_mapping::FromBrackets for $struct {
+            fn from_brackets(
+                bk: &$crate::tools::bracket::Brackets,
+                section: &$crate::tools::bracket::BracketSection
+            ) -> Result<Self, $crate::tools::bracket::bk_error::BracketsError> {
+                Ok($struct {
+                    $(
+                        $field: bk.read_field(section, $crate::bk_mapping!(@key $field $($key)?))?,
+                    )*
+                })
+            }
+        }
+
+        impl $crate::tools::bracket::bk_mapping::ToBrackets for $struct {
+            fn to_brackets(&self) -> $crate::tools::bracket::BracketSection {
+                let fields: Vec<$crate::tools::bracket::BracketSection> = vec![
+                    $(
+                        $crate::tools::bracket::bk_mapping::named_section(
+                            $crate::bk_mapping!(@key $field $($key)?),
+                            $crate::tools::bracket::bk_mapping::ToBrackets::to_brackets(&self.$field)
+                        ),
+                    )*
+                ].into_iter().flatten().collect();
+                $crate::tools::bracket::bk_mapping::object_section(fields)
+            }
+        }
+    };
+}
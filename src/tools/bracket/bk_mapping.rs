@@ -0,0 +1,322 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::date::{datetime::DateTime, DayDate};
+use super::{bk_error::{BracketsError, ErrorCode}, BracketArray, BracketSection, BracketType, BracketValue, Brackets};
+
+pub const MISSING_FIELD: &str = "Missing field";
+pub const MISSING_VALUE: &str = "Missing value";
+pub const INVALID_VALUE: &str = "Invalid value";
+pub const NOT_AN_ARRAY: &str = "Expected an array";
+
+// reading a value from a section, use bk_mapping! to implement it for a struct
+pub trait FromBrackets: Sized {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError>;
+
+    // called when a field is not found in its parent, offset is the parent position
+    fn from_missing(key: &str, offset: Option<usize>) -> Result<Self, BracketsError> {
+        let error = BracketsError::coded(ErrorCode::Mapping, format!("{} '{}'", MISSING_FIELD, key));
+        Err(match offset {
+            Some(o) => error.at(o),
+            None => error
+        })
+    }
+}
+
+// writing a value as a section built like the ones read from json, values are kept in cache
+pub trait ToBrackets {
+    fn to_brackets(&self) -> BracketSection;
+}
+
+impl Brackets {
+    // first section matching the path query, see find
+    pub fn read<T: FromBrackets>(&self, term: &str) -> Result<T, BracketsError> {
+        match self.find(term)?.first() {
+            Some(section) => T::from_brackets(self, section).map_err(|e| self.locate(e)),
+            None => T::from_missing(term, None)
+        }
+    }
+
+    // start of the section in the buffer, leading spaces of names are skipped
+    pub fn get_offset(&self, section: &BracketSection) -> usize {
+        let value = match section {
+            BracketSection::Array(a) => {
+                let a = a.borrow();
+                if a.name.is_empty {
+                    return a.array.first().map_or(a.name.start, |c| self.get_offset(c));
+                }
+                a.name.clone()
+            },
+            BracketSection::Str(v) | BracketSection::Int(v) | BracketSection::Real(v) => v.borrow().clone(),
+            BracketSection::NoVal => return 0
+        };
+        let buffer = self.get_buffer();
+        match buffer.get(value.start..=value.end) {
+            Some(s) if value.end >= value.start => value.start + s.len() - s.trim_start().len(),
+            _ => value.start
+        }
+    }
+
+    // used by bk_mapping!, reads the child named key
+    pub fn read_field<T: FromBrackets>(&self, section: &BracketSection, key: &str) -> Result<T, BracketsError> {
+        match Self::children(section).iter().find(|c| self.has_name(c, key)) {
+            Some(child) => T::from_brackets(self, child),
+            None => T::from_missing(key, Some(self.get_offset(section)))
+        }
+    }
+
+    // position of the single value held by a named array, or of the section itself
+    fn get_value_offset(&self, section: &BracketSection) -> usize {
+        match Self::children(section).as_slice() {
+            [single] if !matches!(single, BracketSection::Array(_) | BracketSection::NoVal) => self.get_offset(single),
+            _ => self.get_offset(section)
+        }
+    }
+
+    fn read_text(&self, section: &BracketSection) -> Result<String, BracketsError> {
+        self.get_value_text(section).ok_or_else(|| {
+            let offset = self.get_value_offset(section);
+            BracketsError::coded(ErrorCode::Mapping, MISSING_VALUE.to_owned()).at(offset)
+        })
+    }
+
+    fn parse_text<T>(&self, section: &BracketSection, parse: impl Fn(&str) -> Option<T>) -> Result<T, BracketsError> {
+        let text = self.read_text(section)?;
+        parse(&text).ok_or_else(|| {
+            let offset = self.get_value_offset(section);
+            BracketsError::coded(ErrorCode::Mapping, format!("{} '{}'", INVALID_VALUE, text)).at(offset)
+        })
+    }
+}
+
+// used by bk_mapping!, fields are stored like json objects: a named array holding the value,
+// or the array itself when the value is one. Absent values (NoVal) are left out
+pub fn named_section(key: &str, value: BracketSection) -> Option<BracketSection> {
+    let array = match value {
+        BracketSection::NoVal => return None,
+        BracketSection::Array(a) => a,
+        v => Rc::new(RefCell::new(BracketArray { array: vec![Rc::new(v)], ..Default::default() }))
+    };
+    array.borrow_mut().name = BracketValue { btyp: BracketType::Name, ..BracketValue::new_value(key) };
+    Some(BracketSection::Array(array))
+}
+
+pub fn object_section(fields: Vec<BracketSection>) -> BracketSection {
+    BracketSection::Array(Rc::new(RefCell::new(BracketArray { array: fields.into_iter().map(Rc::new).collect(), ..Default::default() })))
+}
+
+pub fn array_section(items: Vec<BracketSection>) -> BracketSection {
+    object_section(items.into_iter()
+        .map(|item| match item {
+            BracketSection::Array(_) => item,
+            v => object_section(vec![v])
+        })
+        .collect())
+}
+
+fn value_section(text: String, btyp: BracketType) -> BracketSection {
+    let value = Rc::new(RefCell::new(BracketValue { is_empty: text.is_empty(), btyp: btyp.clone(), ..BracketValue::new_value(&text) }));
+    match btyp {
+        BracketType::Int => BracketSection::Int(value),
+        BracketType::Real => BracketSection::Real(value),
+        _ => BracketSection::Str(value)
+    }
+}
+
+impl FromBrackets for String {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+        bk.read_text(section)
+    }
+}
+
+impl ToBrackets for String {
+    fn to_brackets(&self) -> BracketSection {
+        value_section(self.clone(), BracketType::Simple)
+    }
+}
+
+macro_rules! impl_number {
+    ($btyp:expr, $($t:ty),*) => {
+        $(
+            impl FromBrackets for $t {
+                fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+                    bk.parse_text(section, |s| s.parse::<$t>().ok())
+                }
+            }
+
+            impl ToBrackets for $t {
+                fn to_brackets(&self) -> BracketSection {
+                    value_section(self.to_string(), $btyp)
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(BracketType::Int, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_number!(BracketType::Real, f32, f64);
+
+impl FromBrackets for bool {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+        bk.parse_text(section, |s| s.parse::<bool>().ok())
+    }
+}
+
+impl ToBrackets for bool {
+    fn to_brackets(&self) -> BracketSection {
+        value_section(self.to_string(), BracketType::Simple)
+    }
+}
+
+impl FromBrackets for DayDate {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+        bk.parse_text(section, |s| DayDate::parse(s).ok())
+    }
+}
+
+impl ToBrackets for DayDate {
+    fn to_brackets(&self) -> BracketSection {
+        value_section(self.format("%Y-%m-%d").unwrap_or_default(), BracketType::Date)
+    }
+}
+
+impl FromBrackets for DateTime {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+        bk.parse_text(section, |s| DateTime::parse(s).ok())
+    }
+}
+
+impl ToBrackets for DateTime {
+    fn to_brackets(&self) -> BracketSection {
+        value_section(self.to_rfc3339(), BracketType::Date)
+    }
+}
+
+impl<T: FromBrackets> FromBrackets for Option<T> {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+        match section {
+            BracketSection::NoVal => Ok(None),
+            BracketSection::Array(a) if a.borrow().array.iter().all(|c| matches!(**c, BracketSection::NoVal)) => Ok(None),
+            _ => T::from_brackets(bk, section).map(Some)
+        }
+    }
+
+    fn from_missing(_key: &str, _offset: Option<usize>) -> Result<Self, BracketsError> {
+        Ok(None)
+    }
+}
+
+impl<T: ToBrackets> ToBrackets for Option<T> {
+    fn to_brackets(&self) -> BracketSection {
+        self.as_ref().map_or(BracketSection::NoVal, |v| v.to_brackets())
+    }
+}
+
+impl<T: FromBrackets> FromBrackets for Vec<T> {
+    fn from_brackets(bk: &Brackets, section: &BracketSection) -> Result<Self, BracketsError> {
+        if !matches!(section, BracketSection::Array(_)) {
+            let offset = bk.get_offset(section);
+            return Err(BracketsError::coded(ErrorCode::Mapping, NOT_AN_ARRAY.to_owned()).at(offset));
+        }
+        Brackets::children(section).iter()
+            .filter(|c| !matches!(c, BracketSection::NoVal))
+            .map(|c| T::from_brackets(bk, c))
+            .collect()
+    }
+}
+
+impl<T: ToBrackets> ToBrackets for Vec<T> {
+    fn to_brackets(&self) -> BracketSection {
+        array_section(self.iter().map(|v| v.to_brackets()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bk_mapping;
+
+    #[derive(Debug, PartialEq)]
+    struct Country {
+        name: String,
+        code: String,
+        currency: String
+    }
+
+    bk_mapping!(Country { name, code, currency as "currencyCode" });
+
+    #[derive(Debug, PartialEq)]
+    struct Division {
+        code: u32,
+        label: String,
+        description: Option<String>
+    }
+
+    bk_mapping!(Division { code, label, description });
+
+    #[derive(Debug, PartialEq)]
+    struct Data {
+        countries: Vec<Country>,
+        divisions: Vec<Division>
+    }
+
+    bk_mapping!(Data { countries, divisions });
+
+    fn load_db() -> Brackets {
+        Brackets::build_from_string(include_str!("../../data/db.bk").to_owned()).unwrap()
+    }
+
+    #[test]
+    fn read_structs() {
+        let bk = load_db();
+        let countries: Vec<Country> = bk.read("data.countries").unwrap();
+        assert_eq!(countries[1], Country {
+            name: String::from("United State of America"),
+            code: String::from("USA"),
+            currency: String::from("USD")
+        });
+        let divisions: Vec<Division> = bk.read("data.divisions").unwrap();
+        assert_eq!(divisions.len(), 3);
+        assert_eq!(divisions[0].description, None);
+        assert_eq!(divisions[1].code, 10);
+        assert_eq!(divisions[1].label, "block[|10|]");
+        let data: Data = bk.read("data").unwrap();
+        assert_eq!(data.countries, countries);
+        assert_eq!(data.divisions, divisions);
+    }
+
+    #[test]
+    fn read_errors() {
+        let text = "[\n    items[\n        [\n            name[Swiss]\n        ]\n        [\n            name[France]\n            code[FR]\n            currencyCode[EUR]\n        ]\n    ]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let error = bk.read::<Vec<Country>>("items").unwrap_err();
+        assert_eq!(&text[error.offset().unwrap()..error.offset().unwrap() + 4], "name");
+        assert!(error.to_string().starts_with(MISSING_FIELD));
+
+        let bk = load_db();
+        let error = bk.read::<Vec<Country>>("data.divisions").unwrap_err();
+        assert!(error.to_string().starts_with(MISSING_FIELD));
+        let error = bk.read::<u32>("data.countries[0].code").unwrap_err();
+        assert!(error.to_string().starts_with(INVALID_VALUE));
+        assert_eq!(&include_str!("../../data/db.bk")[error.offset().unwrap()..error.offset().unwrap() + 2], "CH");
+        assert!(bk.read::<Country>("data.unknown").is_err());
+    }
+
+    #[test]
+    fn write_structs() {
+        let data = Data {
+            countries: vec![Country { name: String::from("Swiss"), code: String::from("CH"), currency: String::from("CHF") }],
+            divisions: vec![
+                Division { code: 1, label: String::from("main"), description: None },
+                Division { code: 2, label: String::new(), description: Some(String::from("second")) }
+            ]
+        };
+        let section = data.to_brackets();
+        let bk = Brackets::default();
+        assert_eq!(Data::from_brackets(&bk, &section).unwrap(), data);
+        let fields = Brackets::children(&section);
+        assert_eq!(fields.len(), 2);
+        let countries = Brackets::children(&fields[0]);
+        assert_eq!(bk.named(&countries[0]), None);
+        assert!(bk.has_name(&Brackets::children(&countries[0])[2], "currencyCode"));
+    }
+}
@@ -0,0 +1,226 @@
+use std::{fs::File, io::{self, Write}};
+
+use super::{BracketSection, BracketType, Brackets, ConfigProps, AT_CHAR, CLOSE, COLON_CHAR, COMMA_CHAR, ESCAPE_CHAR, OPEN, PIPE_CHAR};
+
+// layout of the written documents. Values always get the brackets they need: typed values their
+// token, lists [,a,b,] and texts holding brackets a free text delimiter their content cannot close
+#[derive(Debug, Clone)]
+pub struct PrettyConfig {
+    // spaces per level
+    pub indent: usize,
+    // children on their own lines, otherwise on one line separated by a space
+    pub one_per_line: bool,
+    // arrays of single values kept on one line when it is at most this long, 0 to never do so
+    pub inline_width: usize,
+    // writes the configuration of the document first
+    pub header: bool
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig { indent: 4, one_per_line: true, inline_width: 0, header: true }
+    }
+}
+
+impl PrettyConfig {
+    pub fn indent_unit(&self) -> String {
+        " ".repeat(self.indent)
+    }
+}
+
+impl Brackets {
+    // the document as text, config first then one value per line
+    pub fn to_bk_string(&self) -> String {
+        self.to_pretty_string(&PrettyConfig::default())
+    }
+
+    pub fn to_pretty_string(&self, pretty: &PrettyConfig) -> String {
+        let mut text = String::new();
+        if pretty.header {
+            text.push_str(&self.header_to_text(pretty));
+        }
+        text.push_str(&self.brackets_to_text(&self.root.borrow(), "", pretty));
+        text.push('\n');
+        text
+    }
+
+    pub fn write_pretty_into_new_file(&self, full_path: &str, pretty: &PrettyConfig) -> io::Result<File> {
+        let mut file = File::create_new(full_path)?;
+        file.write_all(self.to_pretty_string(pretty).as_bytes())?;
+        Ok(file)
+    }
+
+    fn header_to_text(&self, pretty: &PrettyConfig) -> String {
+        let mut config = self.config.clone();
+        let props: Vec<String> = ConfigProps.iter()
+            .map(|p| (p.replace('_', " "), config.get_config_value(p).to_owned()))
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(p, v)| format!("{}{}{}{}", p, OPEN, v, CLOSE))
+            .collect();
+        if props.is_empty() {
+            return String::new();
+        }
+        if !pretty.one_per_line {
+            return format!("{}{}{}{}\n", AT_CHAR, OPEN, props.join(" "), CLOSE);
+        }
+        let unit = pretty.indent_unit();
+        let lines: String = props.iter().map(|p| format!("{}{}\n", unit, p)).collect();
+        format!("{}{}\n{}{}\n\n", AT_CHAR, OPEN, lines, CLOSE)
+    }
+
+    // name and brackets of a section, children are indented one level more than indent
+    pub(crate) fn section_to_text(&self, section: &BracketSection, indent: &str, pretty: &PrettyConfig) -> String {
+        match self.named(section) {
+            Some(name) => format!("{}{}", Self::name_to_text(&name), self.brackets_to_text(section, indent, pretty)),
+            None => self.brackets_to_text(section, indent, pretty)
+        }
+    }
+
+    pub(crate) fn brackets_to_text(&self, section: &BracketSection, indent: &str, pretty: &PrettyConfig) -> String {
+        let BracketSection::Array(a) = section else { return self.value_to_text(section) };
+        let children = Self::children(section);
+        match children.as_slice() {
+            [] => format!("{}{}", OPEN, CLOSE),
+            [single] if !matches!(single, BracketSection::Array(_)) => self.value_to_text(single),
+            _ => {
+                if !pretty.one_per_line || (pretty.inline_width > 0 && children.iter().all(Self::is_leaf)) {
+                    let items: Vec<String> = children.iter().map(|c| self.section_to_text(c, indent, pretty)).collect();
+                    let text = format!("{}{}{}", OPEN, items.join(" "), CLOSE);
+                    if !pretty.one_per_line || text.chars().count() <= pretty.inline_width {
+                        return text;
+                    }
+                }
+                let inner = format!("{}{}", indent, pretty.indent_unit());
+                let mut text = format!("{}\n", OPEN);
+                for child in a.borrow().array.iter() {
+                    text.push_str(&format!("{}{}\n", inner, self.section_to_text(child, &inner, pretty)));
+                }
+                text.push_str(&format!("{}{}", indent, CLOSE));
+                text
+            }
+        }
+    }
+
+    pub(crate) fn name_to_text(name: &str) -> String {
+        if name.contains(OPEN) || name.contains(CLOSE) {
+            format!("{}{} {} {}{}", OPEN, AT_CHAR, name, AT_CHAR, CLOSE)
+        }
+        else {
+            name.to_owned()
+        }
+    }
+
+    // a single value or a named one: x, name[x]
+    fn is_leaf(section: &BracketSection) -> bool {
+        Self::children(section).iter().all(|c| !matches!(c, BracketSection::Array(_)))
+    }
+
+    // a single value with its brackets: [text], [=int{12}], [,a,b,] or [|free [text]|]
+    fn value_to_text(&self, section: &BracketSection) -> String {
+        let (v, token) = match section {
+            BracketSection::Int(v) => (v, "=int"),
+            BracketSection::Real(v) => (v, "=real"),
+            BracketSection::Str(v) if matches!(v.borrow().btyp, BracketType::Date) => (v, "=date"),
+            BracketSection::Str(v) => (v, ""),
+            _ => return format!("{}{}", OPEN, CLOSE)
+        };
+        let value = self.get_text(&v.borrow());
+        if !token.is_empty() {
+            return format!("{}{}{{{}}}{}", OPEN, token, value, CLOSE);
+        }
+        if value.starts_with(COMMA_CHAR) {
+            return format!("{}{}{}{}", OPEN, value, COMMA_CHAR, CLOSE);
+        }
+        if Self::is_plain_text(&value) {
+            return format!("{}{}{}", OPEN, value, CLOSE);
+        }
+        // free text, the delimiter absent from the text is preferred then repeated until it cannot
+        // be mistaken for the closing, spaces keep it apart from the text, they are trimmed on reading
+        let character = if value.contains(PIPE_CHAR) && !value.contains(COLON_CHAR) { COLON_CHAR } else { PIPE_CHAR };
+        let mut delimiter = character.to_string();
+        while value.contains(&format!("{}{}", delimiter, CLOSE)) || value.contains(&format!("{}{}", OPEN, delimiter)) {
+            delimiter.push(character);
+        }
+        let padding = if value.starts_with(character) || value.ends_with(character) { " " } else { "" };
+        format!("{}{}{}{}{}{}{}", OPEN, delimiter, padding, value, padding, delimiter, CLOSE)
+    }
+
+    // no unescaped bracket and no leading or ending character read as a token
+    fn is_plain_text(value: &str) -> bool {
+        let mut escaped = false;
+        for c in value.chars() {
+            if !escaped && (c == OPEN || c == CLOSE) {
+                return false;
+            }
+            escaped = !escaped && c == ESCAPE_CHAR;
+        }
+        !escaped && !value.starts_with([PIPE_CHAR, COLON_CHAR, AT_CHAR, '=', '*']) && !value.ends_with([PIPE_CHAR, COLON_CHAR, COMMA_CHAR])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn load_db() -> Brackets {
+        Brackets::build_from_string(include_str!("../../data/db.bk").to_owned()).unwrap()
+    }
+
+    #[test]
+    fn pretty_layouts() {
+        let bk = load_db();
+        assert_eq!(bk.to_bk_string(), bk.to_pretty_string(&PrettyConfig::default()));
+
+        let pretty = PrettyConfig { indent: 2, inline_width: 50, header: false, ..Default::default() };
+        let text = bk.to_pretty_string(&pretty);
+        assert!(text.starts_with("[\n  model[\n    currency[,code<3>,symbol,label,]\n"));
+        assert!(text.contains("\n      [name[Swiss] code[CH] currencyCode[CFR]]\n"));
+        assert!(text.contains("\n      [\n        name[United State of America]\n"));
+
+        let pretty = PrettyConfig { one_per_line: false, ..Default::default() };
+        let text = bk.to_pretty_string(&pretty);
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.starts_with("@[name[trees] version[1] trimming[start]]\n[model[currency[,code<3>,symbol,label,] "));
+
+        for text in [text, bk.to_pretty_string(&PrettyConfig { indent: 2, inline_width: 60, ..Default::default() })] {
+            let written = Brackets::build_from_string(text).unwrap();
+            assert_eq!(written.to_json().unwrap(), bk.to_json().unwrap());
+            assert_eq!(written.config.name, "trees");
+        }
+    }
+
+    #[test]
+    fn free_text_delimiters() {
+        let mut bk = Brackets::build_from_string("[\n    a[1]\n]\n".to_owned()).unwrap();
+        let values = ["x[1]", "|piped|", "a|]b", "a:]b|]", "[|x|]", "end:", ":x:", "|a:"];
+        for (i, value) in values.iter().enumerate() {
+            bk.insert("", &format!("v{}", i), &value.to_string()).unwrap();
+        }
+        let text = bk.to_bk_string();
+        assert!(text.contains("v0[|x[1]|]"));
+        assert!(text.contains("v1[:|piped|:]"));
+        assert!(text.contains("v4[:[|x|]:]"));
+        assert!(text.contains("v5[|end:|]"));
+        assert!(text.contains("v6[|:x:|]"));
+        assert!(text.contains("v7[| |a: |]"));
+        assert!(text.contains("v2[:a|]b:]"));
+        assert!(text.contains("v3[||a:]b|]||]"));
+        let written = Brackets::build_from_string(text).unwrap();
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(written.find_values(&format!("v{}", i)).unwrap(), vec![value.to_string()]);
+        }
+    }
+
+    #[test]
+    fn write_pretty_file() {
+        let path = std::env::temp_dir().join(format!("pretty-{}.bk", std::process::id()));
+        let filename = path.to_str().unwrap();
+        let bk = load_db();
+        bk.write_pretty_into_new_file(filename, &PrettyConfig::default()).unwrap();
+        assert_eq!(fs::read_to_string(filename).unwrap(), bk.to_bk_string());
+        assert!(bk.write_pretty_into_new_file(filename, &PrettyConfig::default()).is_err());
+        fs::remove_file(filename).unwrap();
+    }
+}
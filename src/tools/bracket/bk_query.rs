@@ -1,32 +1,295 @@
-use super::{bk_error::BracketsError, BracketSection, Brackets};
+use super::{bk_error::{BracketsError, ErrorCode}, BracketArray, BracketSection, Brackets};
 
 pub const BRACKETS_NO_READY: &str = "Brackets is currently processing data";
 pub const BRACKETS_INVALID: &str = "Brackets data are invalid";
 pub const INVALID_SEARCH_TERM: &str = "Invalid search term";
 
+const FREE_NAME_START: &str = "[@";
+const FREE_NAME_END: &str = "@]";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    // named child: name
+    Child(String),
+    // any named child: *
+    Wildcard,
+    // every item: [*]
+    Items,
+    // item at a position, from 0: [n]
+    Index(usize),
+    // items having a field with a value: [field=value]
+    Filter(String, String),
+    // named descendant at any depth: ..name
+    Descendants(String)
+}
+
 impl Brackets {
     pub fn can_search(&self) -> Result<(), BracketsError> {
         if self.is_processing | self.is_valid.is_none() {
-            return Err(BracketsError::new(BRACKETS_NO_READY));
+            return Err(BracketsError::coded(ErrorCode::Query, BRACKETS_NO_READY.to_owned()));
         }
         if let Some(false) = self.is_valid {
-            return Err(BracketsError::new(BRACKETS_INVALID));
+            return Err(BracketsError::coded(ErrorCode::Query, BRACKETS_INVALID.to_owned()));
         }
         Ok(())
     }
-    
-    pub fn find(&self, term: &str) -> Result<Vec<&BracketSection>, BracketsError> {
+
+    // path query from the root, e.g. data.countries[*].code, data.divisions[code=45].label or ..code,
+    // names holding dots or brackets can be quoted: data."[dbo].[other]"
+    pub fn find(&self, term: &str) -> Result<Vec<BracketSection>, BracketsError> {
         Brackets::find_term_check(term)?;
         self.can_search()?;
-        
-        Ok(vec![])
+        let steps = Self::parse_term(term)?;
+
+        let mut current: Vec<BracketSection> = vec![self.root.borrow().clone()];
+        for step in steps.iter() {
+            current = current.iter().flat_map(|s| self.apply_step(s, step)).collect();
+        }
+        Ok(current)
+    }
+
+    // text of the matching single values
+    pub fn find_values(&self, term: &str) -> Result<Vec<String>, BracketsError> {
+        Ok(self.find(term)?.iter().filter_map(|s| self.get_value_text(s)).collect())
+    }
+
+    pub fn get_value_text(&self, section: &BracketSection) -> Option<String> {
+        match section {
+            BracketSection::Str(v) | BracketSection::Int(v) | BracketSection::Real(v) => Some(self.get_text(&v.borrow())),
+            BracketSection::Array(a) => {
+                let a = a.borrow();
+                match a.array.as_slice() {
+                    [single] => match &**single {
+                        BracketSection::Array(_) => None,
+                        s => self.get_value_text(s)
+                    },
+                    _ => None
+                }
+            },
+            BracketSection::NoVal => None
+        }
+    }
+
+    pub fn get_name(&self, array: &BracketArray) -> String {
+        let name = self.get_text(&array.name);
+        match name.strip_prefix(FREE_NAME_START).and_then(|n| n.strip_suffix(FREE_NAME_END)) {
+            Some(n) => n.trim().to_owned(),
+            None => name
+        }
+    }
+
+    fn apply_step(&self, section: &BracketSection, step: &Step) -> Vec<BracketSection> {
+        let children = Self::children(section);
+        match step {
+            Step::Child(name) => children.into_iter().filter(|c| self.has_name(c, name)).collect(),
+            Step::Wildcard => children.into_iter().filter(|c| self.named(c).is_some()).collect(),
+            Step::Items => children,
+            Step::Index(n) => children.into_iter().nth(*n).into_iter().collect(),
+            Step::Filter(field, value) => children.into_iter()
+                .filter(|c| Self::children(c).iter()
+                    .any(|f| self.has_name(f, field) && self.get_value_text(f).as_deref() == Some(value.as_str())))
+                .collect(),
+            Step::Descendants(name) => {
+                let mut found: Vec<BracketSection> = vec![];
+                for c in children {
+                    if self.has_name(&c, name) {
+                        found.push(c.clone());
+                    }
+                    found.extend(self.apply_step(&c, step));
+                }
+                found
+            }
+        }
+    }
+
+    pub(crate) fn children(section: &BracketSection) -> Vec<BracketSection> {
+        match section {
+            BracketSection::Array(a) => a.borrow().array.iter().map(|c| (**c).clone()).collect(),
+            _ => vec![]
+        }
+    }
+
+    pub(crate) fn named(&self, section: &BracketSection) -> Option<String> {
+        match section {
+            BracketSection::Array(a) if !a.borrow().name.is_empty => {
+                let name = self.get_name(&a.borrow());
+                if name.is_empty() { None } else { Some(name) }
+            },
+            _ => None
+        }
+    }
+
+    pub(crate) fn has_name(&self, section: &BracketSection, name: &str) -> bool {
+        self.named(section).as_deref() == Some(name)
+    }
+
+    fn parse_term(term: &str) -> Result<Vec<Step>, BracketsError> {
+        Ok(Self::read_steps(term)?.into_iter().map(|(_, step)| step).collect())
+    }
+
+    // the term without its last step, which has to be a name, and that name: data."a.b" gives data and a.b
+    pub(crate) fn split_last_name(term: &str) -> Result<(String, String), BracketsError> {
+        let chars: Vec<char> = term.trim().chars().collect();
+        match Self::read_steps(term)?.pop() {
+            Some((start, Step::Child(name))) => {
+                let parent: String = chars[..start].iter().collect();
+                Ok((parent.strip_suffix('.').unwrap_or(&parent).to_owned(), name))
+            },
+            _ => Err(BracketsError::coded(ErrorCode::Query, format!("{}: {}", INVALID_SEARCH_TERM, term)))
+        }
+    }
+
+    // steps with the position of their first character in the trimmed term
+    fn read_steps(term: &str) -> Result<Vec<(usize, Step)>, BracketsError> {
+        let invalid = || BracketsError::coded(ErrorCode::Query, format!("{}: {}", INVALID_SEARCH_TERM, term));
+        let chars: Vec<char> = term.trim().chars().collect();
+        let mut steps: Vec<(usize, Step)> = vec![];
+        let mut i = 0;
+        let mut recursive = false;
+        // a name starts the term or follows a dot, a[0]b is invalid
+        let mut name_expected = true;
+        while i < chars.len() {
+            match chars[i] {
+                '.' => {
+                    if i == 0 && chars.get(1) != Some(&'.') {
+                        return Err(invalid());
+                    }
+                    if chars.get(i + 1) == Some(&'.') {
+                        recursive = true;
+                        i += 1;
+                    }
+                    i += 1;
+                    // a dot has to be followed by a name
+                    if i >= chars.len() || chars[i] == '.' || chars[i] == '[' {
+                        return Err(invalid());
+                    }
+                    name_expected = true;
+                },
+                '[' => {
+                    // a quoted filter value may hold a closing bracket: [label="block[45]"]
+                    let mut quoted = false;
+                    let end = chars[i..].iter().position(|c| {
+                        quoted ^= *c == '"';
+                        *c == ']' && !quoted
+                    }).ok_or_else(invalid)? + i;
+                    let content: String = chars[i + 1..end].iter().collect();
+                    let content = content.trim();
+                    let step = if content == "*" {
+                        Step::Items
+                    }
+                    else if let Ok(n) = content.parse::<usize>() {
+                        Step::Index(n)
+                    }
+                    else if let Some((field, value)) = content.split_once('=') {
+                        if field.trim().is_empty() {
+                            return Err(invalid());
+                        }
+                        let value = value.trim();
+                        let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+                        Step::Filter(field.trim().to_owned(), value.to_owned())
+                    }
+                    else {
+                        return Err(invalid());
+                    };
+                    steps.push((i, step));
+                    name_expected = false;
+                    i = end + 1;
+                },
+                '"' => {
+                    if !name_expected {
+                        return Err(invalid());
+                    }
+                    let end = chars[i + 1..].iter().position(|c| *c == '"').ok_or_else(invalid)? + i + 1;
+                    let name: String = chars[i + 1..end].iter().collect();
+                    steps.push((i, Self::name_step(name, recursive)));
+                    recursive = false;
+                    name_expected = false;
+                    i = end + 1;
+                },
+                _ => {
+                    if !name_expected {
+                        return Err(invalid());
+                    }
+                    let end = chars[i..].iter().position(|c| *c == '.' || *c == '[').map_or(chars.len(), |p| p + i);
+                    let name: String = chars[i..end].iter().collect();
+                    let name = name.trim().to_owned();
+                    if name.is_empty() || name.contains(']') || name.contains('"') {
+                        return Err(invalid());
+                    }
+                    steps.push((i, Self::name_step(name, recursive)));
+                    recursive = false;
+                    name_expected = false;
+                    i = end;
+                }
+            }
+        }
+        Ok(steps)
+    }
+
+    fn name_step(name: String, recursive: bool) -> Step {
+        match (recursive, name.as_str()) {
+            (true, _) => Step::Descendants(name),
+            (false, "*") => Step::Wildcard,
+            _ => Step::Child(name)
+        }
     }
 
     fn find_term_check(term: &str) -> Result<(), BracketsError> {
         if term.trim_end().len() == 0 {
-            return Err(BracketsError::new(INVALID_SEARCH_TERM));
+            return Err(BracketsError::coded(ErrorCode::Query, INVALID_SEARCH_TERM.to_owned()));
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_db() -> Brackets {
+        Brackets::build_from_string(include_str!("../../data/db.bk").to_owned()).unwrap()
+    }
+
+    #[test]
+    fn find_children() {
+        let bk = load_db();
+        assert_eq!(bk.find_values("data.countries[*].code").unwrap(), vec!["CH", "USA"]);
+        assert_eq!(bk.find("model.*").unwrap().len(), 4);
+        assert_eq!(bk.find_values("data.countries[1].name").unwrap(), vec!["United State of America"]);
+        assert!(bk.find("data.countries[5]").unwrap().is_empty());
+        assert!(bk.find("data.unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn find_with_filter() {
+        let bk = load_db();
+        assert_eq!(bk.find_values("data.divisions[code=10].description").unwrap(), vec!["absolute block 10"]);
+        assert_eq!(bk.find_values("data.divisions[code = 45].label").unwrap(), vec!["block[45]"]);
+        assert_eq!(bk.find("data.divisions[code=10]").unwrap().len(), 1);
+        assert_eq!(bk.find_values("data.divisions[code=\"45\"].label").unwrap(), vec!["block[45]"]);
+        assert_eq!(bk.find_values("data.divisions[label=\"block[45]\"].code").unwrap(), vec!["45"]);
+    }
+
+    #[test]
+    fn find_descendants() {
+        let bk = load_db();
+        assert_eq!(bk.find_values("..code").unwrap(), vec!["CH", "USA", "45", "10", "1"]);
+        assert_eq!(bk.find_values("data..currencyCode").unwrap(), vec!["CFR", "USD"]);
+        assert_eq!(bk.find_values("data.\"[dbo].[other]\"").unwrap(), vec!["test \\[\\]"]);
+    }
+
+    #[test]
+    fn invalid_terms() {
+        let bk = load_db();
+        assert!(bk.find("").is_err());
+        assert!(bk.find("data.").is_err());
+        assert!(bk.find(".data").is_err());
+        assert!(bk.find("data[code]").is_err());
+        assert!(bk.find("data[*").is_err());
+        assert!(bk.find("data.\"open").is_err());
+        assert!(bk.find("data.countries[0]code").is_err());
+        assert!(bk.find("data.\"countries\"[0]\"code\"").is_err());
+        assert!(bk.find("\"data\"countries").is_err());
+    }
+}
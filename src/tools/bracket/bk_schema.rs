@@ -0,0 +1,287 @@
+use std::fmt::{Display, Formatter};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::date::{datetime::DateTime, DayDate};
+use super::{bk_error::{BracketsError, ErrorCode, Location}, BracketSection, Brackets};
+
+pub const KEY_MODEL: &str = "model";
+pub const KEY_DATA: &str = "data";
+pub const INVALID_FIELD: &str = "Invalid model field";
+pub const INVALID_HINT: &str = "Invalid model hint";
+pub const NO_MODEL: &str = "No model found";
+
+lazy_static! {
+    static ref RGX_FIELD: Regex = Regex::new(r"^(?<name>[^<>\s]+)\s*(?:<(?<hints>[^<>]*)>)?$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Int,
+    Real,
+    Date
+}
+
+// model fields are written name<hints>, hints being a type (text, int, real, date), a
+// maximum length and/or required, separated by spaces: code<3 required>, id<int>, label<text 20>
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldRule {
+    pub name: String,
+    pub kind: FieldKind,
+    pub max_length: Option<usize>,
+    pub required: bool
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySchema {
+    pub name: String,
+    pub fields: Vec<FieldRule>
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+    pub entities: Vec<EntitySchema>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    MissingField,
+    UnknownField,
+    TooLong(usize),
+    InvalidType(FieldKind)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    pub entity: String,
+    pub index: usize,
+    pub field: String,
+    pub offset: usize,
+    pub location: Location,
+    pub kind: ViolationKind
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let issue = match &self.kind {
+            ViolationKind::MissingField => String::from("is missing"),
+            ViolationKind::UnknownField => String::from("is not in the model"),
+            ViolationKind::TooLong(max) => format!("is longer than {}", max),
+            ViolationKind::InvalidType(kind) => format!("is not a valid {:?}", kind).to_lowercase()
+        };
+        write!(f, "{}[{}].{} {} at line {}, column {}\n{}", self.entity, self.index, self.field, issue, self.location.line, self.location.column, self.location.snippet)
+    }
+}
+
+impl FieldRule {
+    fn parse(text: &str, offset: usize) -> Result<FieldRule, BracketsError> {
+        let captures = RGX_FIELD.captures(text)
+            .ok_or_else(|| BracketsError::coded(ErrorCode::Schema, format!("{} '{}'", INVALID_FIELD, text)).at(offset))?;
+        let mut rule = FieldRule {
+            name: captures["name"].to_owned(),
+            kind: FieldKind::Text,
+            max_length: None,
+            required: false
+        };
+        for hint in captures.name("hints").map_or("", |h| h.as_str()).split_whitespace() {
+            match hint.to_lowercase().as_str() {
+                "text" => rule.kind = FieldKind::Text,
+                "int" => rule.kind = FieldKind::Int,
+                "real" => rule.kind = FieldKind::Real,
+                "date" => rule.kind = FieldKind::Date,
+                "required" => rule.required = true,
+                h => match h.parse::<usize>() {
+                    Ok(n) => rule.max_length = Some(n),
+                    Err(_) => return Err(BracketsError::coded(ErrorCode::Schema, format!("{} '{}'", INVALID_HINT, hint)).at(offset))
+                }
+            }
+        }
+        Ok(rule)
+    }
+
+    fn check(&self, value: &str) -> Option<ViolationKind> {
+        let valid = match self.kind {
+            FieldKind::Text => true,
+            FieldKind::Int => value.parse::<i64>().is_ok(),
+            FieldKind::Real => value.parse::<f64>().is_ok(),
+            FieldKind::Date => DayDate::parse(value).is_ok() || DateTime::parse(value).is_ok()
+        };
+        if !valid {
+            return Some(ViolationKind::InvalidType(self.kind));
+        }
+        match self.max_length {
+            Some(max) if value.chars().count() > max => Some(ViolationKind::TooLong(max)),
+            _ => None
+        }
+    }
+}
+
+impl EntitySchema {
+    pub fn get_field(&self, name: &str) -> Option<&FieldRule> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    // data sections are named after the plural of the entity: country -> countries
+    pub fn matches(&self, section_name: &str) -> bool {
+        let name = self.name.as_str();
+        let mut forms = vec![name.to_owned(), format!("{}s", name), format!("{}es", name)];
+        if let Some(stem) = name.strip_suffix('y') {
+            forms.push(format!("{}ies", stem));
+        }
+        forms.iter().any(|f| f == section_name)
+    }
+}
+
+impl Schema {
+    // reads the model section of the document root
+    pub fn from_brackets(bk: &Brackets) -> Result<Schema, BracketsError> {
+        let model = bk.find(KEY_MODEL)?;
+        let model = model.first().ok_or_else(|| BracketsError::coded(ErrorCode::Schema, NO_MODEL.to_owned()))?;
+        let mut schema = Schema::default();
+        for entity in Brackets::children(model) {
+            let Some(name) = bk.named(&entity) else { continue };
+            let mut fields: Vec<FieldRule> = vec![];
+            if let [BracketSection::Str(v)] = Brackets::children(&entity).as_slice() {
+                let start = bk.get_offset(&Brackets::children(&entity)[0]);
+                let text = bk.get_text(&v.borrow());
+                let mut position = 0;
+                for field in text.split(',') {
+                    let offset = start + position + field.len() - field.trim_start().len();
+                    position += field.len() + 1;
+                    if !field.trim().is_empty() {
+                        fields.push(FieldRule::parse(field.trim(), offset).map_err(|e| bk.locate(e))?);
+                    }
+                }
+            }
+            schema.entities.push(EntitySchema { name, fields });
+        }
+        Ok(schema)
+    }
+
+    pub fn get_entity(&self, section_name: &str) -> Option<&EntitySchema> {
+        self.entities.iter().find(|e| e.matches(section_name))
+    }
+
+    // checks the items of the data sections having an entity in the model, others are ignored
+    pub fn validate(&self, bk: &Brackets) -> Result<Vec<SchemaViolation>, BracketsError> {
+        let mut violations: Vec<SchemaViolation> = vec![];
+        for section in bk.find(&format!("{}.*", KEY_DATA))? {
+            let Some(section_name) = bk.named(&section) else { continue };
+            let Some(entity) = self.get_entity(&section_name) else { continue };
+            for (index, item) in Brackets::children(&section).iter().enumerate() {
+                violations.extend(self.validate_item(bk, entity, &section_name, index, item));
+            }
+        }
+        Ok(violations)
+    }
+
+    fn validate_item(&self, bk: &Brackets, entity: &EntitySchema, section_name: &str, index: usize, item: &BracketSection) -> Vec<SchemaViolation> {
+        let mut violations: Vec<SchemaViolation> = vec![];
+        let violation = |field: &str, offset: usize, kind: ViolationKind| SchemaViolation {
+            entity: section_name.to_owned(),
+            index,
+            field: field.to_owned(),
+            offset,
+            location: Location::find(bk.get_buffer(), offset),
+            kind
+        };
+        let children = Brackets::children(item);
+        for rule in entity.fields.iter().filter(|f| f.required) {
+            if !children.iter().any(|c| bk.has_name(c, &rule.name)) {
+                violations.push(violation(&rule.name, bk.get_offset(item), ViolationKind::MissingField));
+            }
+        }
+        for child in children.iter() {
+            let Some(name) = bk.named(child) else { continue };
+            let offset = bk.get_offset(child);
+            match entity.get_field(&name) {
+                None => violations.push(violation(&name, offset, ViolationKind::UnknownField)),
+                Some(rule) => {
+                    if let Some(kind) = bk.get_value_text(child).and_then(|v| rule.check(&v)) {
+                        violations.push(violation(&name, offset, kind));
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+impl Brackets {
+    // validates the data section against the model section of the document
+    pub fn validate_model(&self) -> Result<Vec<SchemaViolation>, BracketsError> {
+        Schema::from_brackets(self)?.validate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "[\n    model[\n        country[,name<required>, code<3>, rank<int>,]\n    ]\n    data[\n        countries[\n            [\n                name[Swiss]\n                code[CH]\n            ]\n            [\n                code[FRA1]\n                rank[first]\n                size[3]\n            ]\n        ]\n    ]\n]\n";
+
+    #[test]
+    fn read_model() {
+        let bk = Brackets::build_from_string(include_str!("../../data/db.bk").to_owned()).unwrap();
+        let schema = Schema::from_brackets(&bk).unwrap();
+        assert_eq!(schema.entities.len(), 4);
+        let user = schema.get_entity("users").unwrap();
+        assert_eq!(user.fields.len(), 5);
+        assert_eq!(user.get_field("id").unwrap().kind, FieldKind::Int);
+        assert_eq!(user.get_field("countryCode").unwrap().max_length, Some(3));
+        assert!(!user.get_field("firstname").unwrap().required);
+        assert_eq!(schema.get_entity("countries").unwrap().name, "country");
+        assert_eq!(schema.get_entity("currencies").unwrap().name, "currency");
+        assert!(schema.get_entity("other").is_none());
+        assert!(bk.validate_model().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_model() {
+        let text = "[\n    model[\n        country[,code, name<long>,]\n    ]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let error = Schema::from_brackets(&bk).unwrap_err();
+        assert!(error.to_string().starts_with(INVALID_HINT));
+        assert_eq!(&text[error.offset().unwrap()..error.offset().unwrap() + 4], "name");
+        let bk = Brackets::build_from_string("[\n    data[\n        a[1]\n    ]\n]\n".to_owned()).unwrap();
+        assert!(bk.validate_model().is_err());
+    }
+
+    #[test]
+    fn validate_data() {
+        let bk = Brackets::build_from_string(TEXT.to_owned()).unwrap();
+        let violations = bk.validate_model().unwrap();
+        let kinds: Vec<(&str, &ViolationKind)> = violations.iter().map(|v| (v.field.as_str(), &v.kind)).collect();
+        assert_eq!(kinds, vec![
+            ("name", &ViolationKind::MissingField),
+            ("code", &ViolationKind::TooLong(3)),
+            ("rank", &ViolationKind::InvalidType(FieldKind::Int)),
+            ("size", &ViolationKind::UnknownField)
+        ]);
+        assert!(violations.iter().all(|v| v.index == 1 && v.entity == "countries"));
+        assert_eq!(&TEXT[violations[1].offset..violations[1].offset + 4], "code");
+        assert_eq!((violations[1].location.line, violations[1].location.column), (12, 17));
+        assert_eq!(violations[1].to_string(), format!("countries[1].code is longer than 3 at line 12, column 17\n{}", violations[1].location.snippet));
+        assert!(violations[1].location.snippet.starts_with("12 |                 code[FRA1]"));
+    }
+
+    #[test]
+    fn required_hint() {
+        let rule = FieldRule::parse("code<3 Required>", 0).unwrap();
+        assert!(rule.required);
+        assert_eq!((rule.kind, rule.max_length), (FieldKind::Text, Some(3)));
+        assert!(!FieldRule::parse("code<3>", 0).unwrap().required);
+    }
+
+    #[test]
+    fn check_dates() {
+        let rule = FieldRule::parse("at<date>", 0).unwrap();
+        assert_eq!(rule.check("2024-01-02"), None);
+        assert_eq!(rule.check("2024-01-02T10:00:00Z"), None);
+        assert_eq!(rule.check("2024-01-02T10:00:00.5+02:00"), None);
+        assert_eq!(rule.check("2024-13-02"), Some(ViolationKind::InvalidType(FieldKind::Date)));
+        assert_eq!(rule.check("tomorrow"), Some(ViolationKind::InvalidType(FieldKind::Date)));
+    }
+}
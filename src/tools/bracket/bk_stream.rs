@@ -0,0 +1,606 @@
+use std::{collections::VecDeque, fs::File, io::{ErrorKind, Read}};
+
+use super::{bk_error::{BracketsError, ErrorCode}, trim_text, BracketConfig, Brackets, ConfigProps, ALLOW_MODE_ON, AT_CHAR, CLOSE, CLOSURE_MODE_RAW, COLON_CHAR, COMMA_CHAR, ESCAPE_CHAR, OPEN, PIPE_CHAR};
+
+const CHUNK_SIZE: usize = 8192;
+const TYPED_TOKENS: [(&str, ValueKind); 3] = [("=int{", ValueKind::Int), ("=real{", ValueKind::Real), ("=date{", ValueKind::Date)];
+const TYPE_END: &str = "}]";
+const LIST_END: &str = ",]";
+const NAME_END: &str = "@]";
+const COMMENT_START: &str = "**[";
+const COMMENT_END: &str = "]**";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    Text,
+    List,
+    Int,
+    Real,
+    Date
+}
+
+// arrays are reported like in the tree: code[CH] is an array named code holding the value CH,
+// values come without their delimiters and trimmed as the trimming config tells, lists keep their
+// leading comma: ,a,b for [,a,b,]. Empty values are not reported. Config names are the BracketConfig keys
+#[derive(Debug, Clone, PartialEq)]
+pub enum BracketEvent {
+    Config(String, String),
+    StartArray(Option<String>),
+    Value(ValueKind, String),
+    EndArray
+}
+
+// SAX-style reading, see Brackets::visit
+pub trait BracketsVisitor {
+    fn config(&mut self, _name: &str, _value: &str) {}
+    fn start_array(&mut self, _name: Option<&str>) {}
+    fn value(&mut self, _kind: ValueKind, _text: &str) {}
+    fn end_array(&mut self) {}
+}
+
+// bytes of the reader, only the part not read yet is kept. Tokens are ascii so scanning bytes
+// never splits a character, texts are decoded once complete
+struct Source<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    offset: usize,
+    eof: bool
+}
+
+impl<R: Read> Source<R> {
+    // makes n bytes available unless the end is reached first
+    fn fill(&mut self, n: usize) -> Result<(), BracketsError> {
+        let mut chunk = [0u8; CHUNK_SIZE];
+        while self.buffer.len() - self.position < n && !self.eof {
+            self.buffer.drain(..self.position);
+            self.offset += self.position;
+            self.position = 0;
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                Err(e) => return Err(BracketsError::coded(ErrorCode::Io, format!("{}: {}", ErrorCode::Io.message(), e)).at(self.offset()))
+            }
+        }
+        Ok(())
+    }
+
+    fn peek_at(&mut self, i: usize) -> Result<Option<u8>, BracketsError> {
+        self.fill(i + 1)?;
+        Ok(self.buffer.get(self.position + i).copied())
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, BracketsError> {
+        self.peek_at(0)
+    }
+
+    fn starts_with(&mut self, token: &str) -> Result<bool, BracketsError> {
+        self.fill(token.len())?;
+        Ok(self.buffer[self.position..].starts_with(token.as_bytes()))
+    }
+
+    fn bump(&mut self, n: usize) {
+        self.position = (self.position + n).min(self.buffer.len());
+    }
+
+    fn offset(&self) -> usize {
+        self.offset + self.position
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), BracketsError> {
+        while self.peek()?.is_some_and(|b| b.is_ascii_whitespace()) {
+            self.bump(1);
+        }
+        Ok(())
+    }
+
+    // bytes up to the end token which is consumed, None when the reader ends first
+    fn read_until(&mut self, end: &str) -> Result<Option<Vec<u8>>, BracketsError> {
+        let mut bytes: Vec<u8> = vec![];
+        loop {
+            if self.starts_with(end)? {
+                self.bump(end.len());
+                return Ok(Some(bytes));
+            }
+            match self.peek()? {
+                Some(b) => {
+                    bytes.push(b);
+                    self.bump(1);
+                },
+                None => return Ok(None)
+            }
+        }
+    }
+}
+
+// pull parser reading any source with bounded memory: the longest name or value and the offsets
+// of the open arrays. Positions of the errors are offsets in the source
+pub struct BracketsStream<R: Read> {
+    source: Source<R>,
+    opens: Vec<usize>,
+    pending: VecDeque<BracketEvent>,
+    config: BracketConfig,
+    // the last event closed an array, the text up to the next bracket is not a value
+    after_child: bool,
+    started: bool,
+    failed: bool
+}
+
+impl<R: Read> BracketsStream<R> {
+    pub fn new(reader: R) -> BracketsStream<R> {
+        Self::with_config(reader, Default::default())
+    }
+
+    // the configuration of the document, if any, overrides the given one
+    pub fn with_config(reader: R, config: BracketConfig) -> BracketsStream<R> {
+        BracketsStream {
+            source: Source { reader, buffer: Vec::with_capacity(CHUNK_SIZE), position: 0, offset: 0, eof: false },
+            opens: vec![],
+            pending: VecDeque::new(),
+            config,
+            after_child: false,
+            started: false,
+            failed: false
+        }
+    }
+
+    // complete once the config of the document is read, i.e. from the first StartArray
+    pub fn config(&self) -> &BracketConfig {
+        &self.config
+    }
+
+    // number of arrays open
+    pub fn depth(&self) -> usize {
+        self.opens.len()
+    }
+
+    pub fn next_event(&mut self) -> Result<Option<BracketEvent>, BracketsError> {
+        if self.failed {
+            return Ok(None);
+        }
+        let event = self.read_event();
+        match event {
+            Ok(Some(BracketEvent::StartArray(_))) => self.after_child = false,
+            Ok(Some(BracketEvent::EndArray)) => self.after_child = true,
+            _ => self.failed = event.is_err()
+        }
+        event
+    }
+
+    fn read_event(&mut self) -> Result<Option<BracketEvent>, BracketsError> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+        if self.opens.is_empty() {
+            return self.read_outside();
+        }
+        let text = self.read_text()?;
+        let offset = self.source.offset();
+        match self.source.peek()? {
+            None => Err(self.unclosed()),
+            Some(b) if b == CLOSE as u8 => {
+                self.source.bump(1);
+                self.opens.pop();
+                match self.value(ValueKind::Text, &text).filter(|_| !self.after_child) {
+                    Some(value) => {
+                        self.pending.push_back(BracketEvent::EndArray);
+                        Ok(Some(value))
+                    },
+                    None => Ok(Some(BracketEvent::EndArray))
+                }
+            },
+            Some(_) => {
+                self.source.bump(1);
+                let name = Some(text.trim().to_owned()).filter(|n| !n.is_empty());
+                self.read_open(name, offset)
+            }
+        }
+    }
+
+    // before and after the root: comments, the config then the root
+    fn read_outside(&mut self) -> Result<Option<BracketEvent>, BracketsError> {
+        loop {
+            self.source.skip_whitespace()?;
+            let offset = self.source.offset();
+            if self.source.starts_with(COMMENT_START)? {
+                self.source.bump(COMMENT_START.len());
+                self.read_comment(offset)?;
+                continue;
+            }
+            match self.source.peek()? {
+                None if self.started => return Ok(None),
+                None => return Err(BracketsError::from_code(ErrorCode::EmptyString).at(offset)),
+                Some(b) if !self.started && b == AT_CHAR as u8 && self.source.peek_at(1)? == Some(OPEN as u8) => {
+                    self.source.bump(2);
+                    self.read_config(offset)?;
+                    if let Some(event) = self.pending.pop_front() {
+                        return Ok(Some(event));
+                    }
+                },
+                Some(b) if !self.started && b == OPEN as u8 => {
+                    self.source.bump(1);
+                    self.started = true;
+                    self.opens.push(offset);
+                    return Ok(Some(BracketEvent::StartArray(None)));
+                },
+                Some(b) if b == CLOSE as u8 => return Err(BracketsError::from_code(ErrorCode::BadEnding).at(offset)),
+                Some(_) => return Err(BracketsError::from_code(ErrorCode::BaseFormat).at(offset))
+            }
+        }
+    }
+
+    // the opening bracket is read, the token following it tells the kind of the array
+    fn read_open(&mut self, name: Option<String>, offset: usize) -> Result<Option<BracketEvent>, BracketsError> {
+        for (token, kind) in TYPED_TOKENS {
+            if self.source.starts_with(token)? {
+                self.source.bump(token.len());
+                let text = self.read_closed(TYPE_END, offset)?;
+                let value = self.value(kind, &text);
+                return Ok(Some(self.single(name, value)));
+            }
+        }
+        match self.source.peek()?.map(char::from) {
+            Some(COMMA_CHAR) => {
+                self.source.bump(1);
+                let text = self.read_closed(LIST_END, offset)?;
+                let value = self.value(ValueKind::List, &format!("{}{}", COMMA_CHAR, text));
+                Ok(Some(self.single(name, value)))
+            },
+            Some(c) if c == PIPE_CHAR || c == COLON_CHAR => {
+                let mut quantity = 0;
+                while self.source.peek_at(quantity)? == Some(c as u8) {
+                    quantity += 1;
+                }
+                // [||||] is an empty free text delimited by ||
+                let text = if quantity % 2 == 0 && self.source.peek_at(quantity)? == Some(CLOSE as u8) {
+                    self.source.bump(quantity + 1);
+                    String::new()
+                }
+                else {
+                    self.source.bump(quantity);
+                    let delimiter = c.to_string().repeat(quantity);
+                    let text = self.read_closed(&format!("{}{}", delimiter, CLOSE), offset)?;
+                    self.check_mask(&text, &delimiter, offset)?;
+                    text
+                };
+                let value = match text.is_empty() && self.config.allow_empty_free_text == ALLOW_MODE_ON {
+                    true => Some(BracketEvent::Value(ValueKind::Text, text)),
+                    false => self.value(ValueKind::Text, &text)
+                };
+                Ok(Some(self.single(name, value)))
+            },
+            Some(AT_CHAR) => {
+                self.source.bump(1);
+                let free_name = self.read_closed(NAME_END, offset)?.trim().to_owned();
+                let open = self.source.offset();
+                if self.source.peek()? != Some(OPEN as u8) {
+                    return Err(BracketsError::from_code(ErrorCode::Format).at(open));
+                }
+                self.source.bump(1);
+                self.read_open(Some(free_name), open)
+            },
+            _ => {
+                self.opens.push(offset);
+                Ok(Some(BracketEvent::StartArray(name)))
+            }
+        }
+    }
+
+    fn single(&mut self, name: Option<String>, value: Option<BracketEvent>) -> BracketEvent {
+        self.pending.extend(value);
+        self.pending.push_back(BracketEvent::EndArray);
+        BracketEvent::StartArray(name)
+    }
+
+    // the value trimmed like in the tree, None when nothing is left
+    fn value(&self, kind: ValueKind, text: &str) -> Option<BracketEvent> {
+        let text = trim_text(text, &self.config.trimming);
+        (!text.is_empty()).then(|| BracketEvent::Value(kind, text.to_owned()))
+    }
+
+    // in raw closure mode, a free text holding a closing bracket then its own opening token is refused:
+    // the delimiter may hide the end of the array
+    fn check_mask(&self, text: &str, delimiter: &str, offset: usize) -> Result<(), BracketsError> {
+        if self.config.closure_mode != CLOSURE_MODE_RAW {
+            return Ok(());
+        }
+        let masked = text.find(&format!("{}{}", OPEN, delimiter))
+            .is_some_and(|i| text[..i].contains(CLOSE));
+        if masked {
+            return Err(BracketsError::coded(ErrorCode::Format, format!("{}, potential error", ErrorCode::Format.message())).at(offset));
+        }
+        Ok(())
+    }
+
+    // text up to the end token, the array opened at offset is then closed
+    fn read_closed(&mut self, end: &str, offset: usize) -> Result<String, BracketsError> {
+        match self.source.read_until(end)? {
+            Some(bytes) => Self::decode(bytes, offset),
+            None => Err(BracketsError::from_code(ErrorCode::Unclosed).at(offset))
+        }
+    }
+
+    // text up to the next bracket which is not read, escaped brackets and comments are skipped
+    fn read_text(&mut self) -> Result<String, BracketsError> {
+        let offset = self.source.offset();
+        let mut bytes: Vec<u8> = vec![];
+        while let Some(b) = self.source.peek()? {
+            if b == ESCAPE_CHAR as u8 {
+                bytes.push(b);
+                self.source.bump(1);
+                if let Some(escaped) = self.source.peek()? {
+                    bytes.push(escaped);
+                    self.source.bump(1);
+                }
+                continue;
+            }
+            if b == OPEN as u8 && bytes.ends_with(&COMMENT_START.as_bytes()[..2]) {
+                bytes.truncate(bytes.len() - 2);
+                let start = self.source.offset() - 2;
+                self.source.bump(1);
+                self.read_comment(start)?;
+                continue;
+            }
+            if b == OPEN as u8 || b == CLOSE as u8 {
+                break;
+            }
+            bytes.push(b);
+            self.source.bump(1);
+        }
+        Self::decode(bytes, offset)
+    }
+
+    fn read_comment(&mut self, offset: usize) -> Result<(), BracketsError> {
+        match self.source.read_until(COMMENT_END)? {
+            Some(_) => Ok(()),
+            None => Err(BracketsError::from_code(ErrorCode::BadEndingComment).at(offset))
+        }
+    }
+
+    // @[name[value] ...], the properties are queued and, when not empty, override the given config
+    fn read_config(&mut self, offset: usize) -> Result<(), BracketsError> {
+        loop {
+            let name = self.read_text()?;
+            match self.source.peek()? {
+                None => return Err(BracketsError::from_code(ErrorCode::Unclosed).at(offset)),
+                Some(b) if b == CLOSE as u8 => {
+                    self.source.bump(1);
+                    return Ok(());
+                },
+                Some(_) => {
+                    let start = self.source.offset();
+                    let key = name.trim().replace(' ', "_");
+                    if !ConfigProps.contains(&key) {
+                        return Err(BracketsError::coded(ErrorCode::InvalidConfig, format!("{}, unknown property '{}'", ErrorCode::InvalidConfig.message(), name.trim())).at(start));
+                    }
+                    self.source.bump(1);
+                    // as in the tree, only a value written empty keeps the given config
+                    let value = self.read_closed(&CLOSE.to_string(), start)?;
+                    if !value.is_empty() {
+                        self.config.set_config(&key, &value);
+                    }
+                    self.pending.push_back(BracketEvent::Config(key, value.trim().to_owned()));
+                }
+            }
+        }
+    }
+
+    fn unclosed(&self) -> BracketsError {
+        let offset = self.opens.last().copied().unwrap_or_default();
+        BracketsError::from_code(ErrorCode::Unclosed).at(offset)
+    }
+
+    fn decode(bytes: Vec<u8>, offset: usize) -> Result<String, BracketsError> {
+        String::from_utf8(bytes).map_err(|_| BracketsError::from_code(ErrorCode::Format).at(offset))
+    }
+}
+
+impl<R: Read> Iterator for BracketsStream<R> {
+    type Item = Result<BracketEvent, BracketsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+impl Brackets {
+    pub fn stream<R: Read>(reader: R) -> BracketsStream<R> {
+        BracketsStream::new(reader)
+    }
+
+    pub fn stream_file(filename: &str) -> Result<BracketsStream<File>, BracketsError> {
+        File::open(filename)
+            .map(BracketsStream::new)
+            .map_err(|e| BracketsError::coded(ErrorCode::Io, format!("Cannot open file {} {}", filename, e)))
+    }
+
+    // reads the whole source, calling the visitor for each event
+    pub fn visit<R: Read>(reader: R, visitor: &mut impl BracketsVisitor) -> Result<(), BracketsError> {
+        for event in Self::stream(reader) {
+            match event? {
+                BracketEvent::Config(name, value) => visitor.config(&name, &value),
+                BracketEvent::StartArray(name) => visitor.start_array(name.as_deref()),
+                BracketEvent::Value(kind, text) => visitor.value(kind, &text),
+                BracketEvent::EndArray => visitor.end_array()
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::bracket::BracketSection;
+    use BracketEvent::*;
+
+    const TEXT: &str = "@[\n    name[test]\n]\n**[ values ]**\n[\n    a[=int{12}]\n    l[,x,y,]\n    f[||t[|1|]||]\n    [@ n[1] @][v \\]]\n    **[ items ]**\n    items[\n        [ x ]\n        d[=date{2024-05-15}]\n    ]\n    e[]\n]\n";
+
+    // returns the source one byte at a time
+    struct Slow<'a>(&'a [u8]);
+
+    impl Read for Slow<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((b, rest)) if !buf.is_empty() => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Ok(1)
+                },
+                _ => Ok(0)
+            }
+        }
+    }
+
+    fn name(n: &str) -> BracketEvent {
+        StartArray(Some(n.to_owned()))
+    }
+
+    fn value(kind: ValueKind, text: &str) -> BracketEvent {
+        Value(kind, text.to_owned())
+    }
+
+    #[test]
+    fn read_events() {
+        let events: Vec<BracketEvent> = Brackets::stream(TEXT.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(events, vec![
+            Config(String::from("name"), String::from("test")),
+            StartArray(None),
+            name("a"), value(ValueKind::Int, "12"), EndArray,
+            name("l"), value(ValueKind::List, ",x,y"), EndArray,
+            name("f"), value(ValueKind::Text, "t[|1|]"), EndArray,
+            name("n[1]"), value(ValueKind::Text, "v \\]"), EndArray,
+            name("items"),
+            StartArray(None), value(ValueKind::Text, "x "), EndArray,
+            name("d"), value(ValueKind::Date, "2024-05-15"), EndArray,
+            EndArray,
+            name("e"), EndArray,
+            EndArray
+        ]);
+        let slow: Vec<BracketEvent> = Brackets::stream(Slow(TEXT.as_bytes())).collect::<Result<_, _>>().unwrap();
+        assert_eq!(slow, events);
+    }
+
+    #[test]
+    fn read_config() {
+        let text = "@[\n    allow empty free text[on]\n    closure mode[raw]\n    trimming[off]\n]\n[\n    a[ x ]\n    e[||||]\n    n[]\n]\n";
+        let mut stream = Brackets::stream(text.as_bytes());
+        let events: Vec<BracketEvent> = stream.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(events, vec![
+            Config(String::from("allow_empty_free_text"), String::from("on")),
+            Config(String::from("closure_mode"), String::from("raw")),
+            Config(String::from("trimming"), String::from("off")),
+            StartArray(None),
+            name("a"), value(ValueKind::Text, " x "), EndArray,
+            name("e"), value(ValueKind::Text, ""), EndArray,
+            name("n"), EndArray,
+            EndArray
+        ]);
+        assert_eq!(stream.config().trimming, "off");
+
+        let mut config = BracketConfig::default();
+        config.trimming = String::from("full");
+        let events: Vec<BracketEvent> = BracketsStream::with_config("[\n    a[ x ]\n    e[||||]\n]".as_bytes(), config)
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(events, vec![StartArray(None), name("a"), value(ValueKind::Text, "x"), EndArray, name("e"), EndArray, EndArray]);
+
+        let masked = "[\n    f[| a ] b [| c |]\n]";
+        assert!(Brackets::stream(masked.as_bytes()).all(|e| e.is_ok()));
+        let error = Brackets::stream(format!("@[\n    closure mode[raw]\n]\n{}", masked).as_bytes()).find_map(|e| e.err()).unwrap();
+        assert_eq!(error.code(), ErrorCode::Format);
+        let error = Brackets::stream("@[\n    colour[red]\n]\n[a[1]]".as_bytes()).find_map(|e| e.err()).unwrap();
+        assert_eq!(error.code(), ErrorCode::InvalidConfig);
+    }
+
+    // events of the tree, names as queries read them and values with the trimming of the document
+    fn tree_events(bk: &Brackets, section: &BracketSection, events: &mut Vec<String>) {
+        match section {
+            BracketSection::Array(array) => {
+                let array = array.borrow();
+                events.push(format!("start {}", bk.get_name(&array)));
+                array.array.iter().for_each(|child| tree_events(bk, child, events));
+                events.push(String::from("end"));
+            },
+            BracketSection::Str(v) | BracketSection::Int(v) | BracketSection::Real(v) => {
+                events.push(format!("value {}", v.borrow().extract_string_from(bk.get_buffer(), bk.get_trim_mode())));
+            },
+            BracketSection::NoVal => {}
+        }
+    }
+
+    #[test]
+    fn same_as_tree() {
+        let source = include_str!("../../data/db.bk");
+        let bk = Brackets::build_from_string(source.to_owned()).unwrap();
+        let mut expected: Vec<String> = vec![];
+        tree_events(&bk, &bk.root.borrow(), &mut expected);
+        // the root takes the name of its first child
+        expected[0] = String::from("start ");
+        let events: Vec<String> = Brackets::stream(Slow(source.as_bytes()))
+            .filter_map(|e| match e.unwrap() {
+                StartArray(name) => Some(format!("start {}", name.unwrap_or_default())),
+                Value(_, text) => Some(format!("value {}", text)),
+                EndArray => Some(String::from("end")),
+                Config(..) => None
+            })
+            .collect();
+        assert_eq!(events, expected);
+    }
+
+    #[derive(Default)]
+    struct Codes {
+        names: Vec<Option<String>>,
+        codes: Vec<String>,
+        config: Vec<String>
+    }
+
+    impl BracketsVisitor for Codes {
+        fn config(&mut self, name: &str, value: &str) {
+            self.config.push(format!("{}={}", name, value));
+        }
+
+        fn start_array(&mut self, name: Option<&str>) {
+            self.names.push(name.map(|n| n.to_owned()));
+        }
+
+        fn value(&mut self, _kind: ValueKind, text: &str) {
+            if let Some(Some(name)) = self.names.last() {
+                if name == "code" {
+                    self.codes.push(text.to_owned());
+                }
+            }
+        }
+
+        fn end_array(&mut self) {
+            self.names.pop();
+        }
+    }
+
+    #[test]
+    fn visit_document() {
+        let source = include_str!("../../data/db.bk");
+        let mut visitor = Codes::default();
+        Brackets::visit(Slow(source.as_bytes()), &mut visitor).unwrap();
+        let bk = Brackets::build_from_string(source.to_owned()).unwrap();
+        assert_eq!(visitor.codes, bk.find_values("..code").unwrap());
+        assert_eq!(visitor.config, vec!["name=trees", "version=1", "trimming=start"]);
+        assert!(visitor.names.is_empty());
+    }
+
+    #[test]
+    fn stream_errors() {
+        let error = |text: &str| Brackets::stream(text.as_bytes()).find_map(|e| e.err()).unwrap();
+        let unclosed = error("[\n    a[1]\n    b[\n");
+        assert_eq!((unclosed.code(), unclosed.offset()), (ErrorCode::Unclosed, Some(16)));
+        assert_eq!(error("[a[1]]]").code(), ErrorCode::BadEnding);
+        assert_eq!(error("[a[1]] b").code(), ErrorCode::BaseFormat);
+        assert_eq!(error("[a[=int{1]]").code(), ErrorCode::Unclosed);
+        assert_eq!(error("**[ open [a[1]]").code(), ErrorCode::BadEndingComment);
+        assert_eq!(error("  ").code(), ErrorCode::EmptyString);
+        let mut stream = Brackets::stream("[a[1]]]".as_bytes());
+        assert_eq!(stream.by_ref().filter(|e| e.is_err()).count(), 1);
+        assert!(stream.next().is_none());
+        assert!(Brackets::stream_file("/unknown/file.bk").is_err());
+    }
+}
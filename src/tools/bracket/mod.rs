@@ -1,15 +1,11 @@
 /* sample text in data/db.bk */
 use std::{cell::RefCell, cmp::Ordering, collections::HashMap, fs::File, io::Read, ops::RangeInclusive, path::Path, rc::Rc};
-use bk_error::{BAD_ENDING_COMMENT, BAD_START_COMMENT};
 use lazy_static::lazy_static;
 use memmap2::{Mmap, MmapOptions};
 use uuid::Uuid;
 use lexical_parse_integer::FromLexical;
-use crate::{bk_config, date::DayDate, tools::bracket::bk_error::{WARNING_ESCAPED, WARNING_FREE_TEXT}};
-use self::bk_error::{
-    BracketsError, BASE_FORMAT_ERROR, EMPTY_STRING, FORMAT_ERROR, INVALID_CONFIG,
-    WARNING_EMPTY_FREE_TEXT, WARNING_MASK,
-};
+use crate::{bk_config, date::{datetime::DateTime, DayDate}, fiscalyear::FiscalYear, tools::bracket::bk_error::{WARNING_ESCAPED, WARNING_FREE_NAME, WARNING_FREE_TEXT}};
+use self::bk_error::{warning_from_name, BracketsError, ErrorCode, STRICT_ALL, WARNING_EMPTY_CONFIG, WARNING_EMPTY_FREE_TEXT, WARNING_MASK};
 
 pub const OPEN: char = '[';
 pub const CLOSE: char = ']';
@@ -31,6 +27,7 @@ const RE_END: &str = r"](?:\*{2})?\s*$";
 
 const CLOSURE_MODE_SMART: &str = "smart";
 const CLOSURE_MODE_RAW: &str = "raw";
+const ALLOW_MODE_ON: &str = "on";
 const CACHE_MODE_OFF: &str = "off";
 const CACHE_MODE_ON: &str = "on";
 const TRIM_MODE_START: &str = "start";
@@ -39,7 +36,7 @@ const TRIM_MODE_END: &str = "end";
 const TRIM_MODE_FULL: &str = "full";
 const TRIM_MODE_OFF: &str = "off";
 
-bk_config!(name, version, allow_empty_free_text, closure_mode, cache, trimming);
+bk_config!(name, version, allow_empty_free_text, closure_mode, cache, trimming, strict);
 
 lazy_static! {
     static ref TOKEN_PIPE: String = format!("{}{}", OPEN, PIPE_CHAR);
@@ -99,6 +96,20 @@ lazy_static! {
 
 type RefStr = Rc<RefCell<String>>;
 
+pub(crate) fn trim_text<'a>(slice: &'a str, trim_mode: &str) -> &'a str {
+    if trim_mode == TRIM_MODE_OFF {
+        return slice
+    }
+    if trim_mode == TRIM_MODE_END {
+        return slice.trim_end()
+    }
+    if trim_mode == TRIM_MODE_FULL {
+        return slice.trim()
+    }
+    
+    slice.trim_start()
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct BracketValue {
     pub start: usize,
@@ -132,18 +143,7 @@ impl BracketValue {
     }
 
     pub fn extract_string_from<'a>(&self, source_buffer: &'a str, trim_mode: &str) -> &'a str {
-        let slice: &'a str = &source_buffer[self.start..=self.end];
-        if trim_mode == TRIM_MODE_OFF {
-            return slice
-        }
-        if trim_mode == TRIM_MODE_END {
-            return slice.trim_end()
-        }
-        if trim_mode == TRIM_MODE_FULL {
-            return slice.trim()
-        }
-        
-        slice.trim_start()
+        trim_text(&source_buffer[self.start..=self.end], trim_mode)
     }
 
     pub fn extract_int_from(&self, buffer: &str, trim_mode: &str) -> Option<isize> {
@@ -202,6 +202,24 @@ impl BracketValue {
             Ok(d) => Some(d)
         }
     }
+
+    pub fn extract_datetime_from(&self, buffer: &str, trim_mode: &str) -> Option<DateTime> {
+        match self.btyp {
+            BracketType::Date => {},
+            _ => { return None }
+        }
+        let text = self.extract_string_from(buffer, trim_mode);
+        DateTime::parse(text).ok()
+    }
+
+    pub fn extract_relative_date_from(&self, buffer: &str, trim_mode: &str, reference: &DayDate, years: &[FiscalYear]) -> Option<DayDate> {
+        match self.btyp {
+            BracketType::Date => {},
+            _ => { return None }
+        }
+        let text = self.extract_string_from(buffer, trim_mode);
+        DayDate::parse_relative(text, reference, years).ok()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -483,9 +501,7 @@ impl BracketChunk {
             return match self.typ {
                 BracketType::FreeText(s) => s.start + s.quantity + 1,
                 BracketType::List => self.idx + 2,
-                BracketType::Date => self.idx + TOKEN_DATE.len(),
-                BracketType::Int => self.idx + TOKEN_INT.len(),
-                BracketType::Real => self.idx + TOKEN_REAL.len(),
+                BracketType::Date | BracketType::Int | BracketType::Real => self.idx + TOKEN_TYPE_END.len(),
                 _ => self.idx + 1
             }
         }
@@ -506,6 +522,7 @@ pub struct Brackets {
     open_bks_hash: HashMap<usize, usize>,
     close_bks_hash: HashMap<usize, usize>,
     flags: Vec<BracketFlag>,
+    warnings: Vec<BracketChunk>,
     is_processing: bool,
     is_valid: Option<bool>,
     start_index: Option<usize>,
@@ -539,6 +556,7 @@ impl Default for Brackets {
             all_open_bks: Default::default(),
             all_close_bks: Default::default(),
             flags: Default::default(),
+            warnings: Default::default(),
             root: Default::default(),
             file_name: Default::default(),
             is_processing: false,
@@ -566,6 +584,15 @@ impl Brackets {
         Ok(new)
     }
 
+    // the configuration of the document, if any, overrides the given one
+    pub fn build_with_config(text: String, config: BracketConfig) -> Result<Brackets, BracketsError> {
+        let mut new = Brackets::default();
+        new.buffer = text;
+        new.config = config;
+        new.process_buffer()?;
+        Ok(new)
+    }
+
     pub fn build_from_file_string(file: &mut File, file_name: &str) -> Result<Brackets, BracketsError> {
         let mut buf: String = Default::default();
         let result = file.read_to_string(&mut buf);
@@ -575,19 +602,19 @@ impl Brackets {
             return Ok(bk)
         }
 
-        Err(BracketsError::new(format!("Could not read file: {}", result.err().unwrap()).as_str()))
+        Err(BracketsError::coded(ErrorCode::Io, format!("Could not read file: {}", result.err().unwrap())))
     }
 
     pub fn build_from_file_map(filename: &str) -> Result<Brackets, BracketsError> {
         let result = File::open(filename);
         let file: File;
         if let Err(e) = result {
-            return Err(BracketsError::new(&format!("Cannot open file {} {}", filename, e)))
+            return Err(BracketsError::coded(ErrorCode::Io, format!("Cannot open file {} {}", filename, e)))
         }
         file = result.unwrap();
         let m = unsafe { MmapOptions::new().map(&file) };
         if let Err(e) = m {
-            return Err(BracketsError::new(&format!("OS error {} occured", e.raw_os_error().unwrap())));
+            return Err(BracketsError::coded(ErrorCode::Io, format!("OS error {} occured", e.raw_os_error().unwrap())));
         }
         
         let mut new = Brackets::default();
@@ -651,6 +678,18 @@ impl Brackets {
         start_index
     }
 
+    // text of a value or a name, from the cache or from the buffer
+    pub fn get_text(&self, value: &BracketValue) -> String {
+        let cached = value.value.borrow();
+        if !cached.is_empty() {
+            return cached.trim().to_owned();
+        }
+        if value.is_empty || value.end < value.start || value.end >= self.get_buffer().len() {
+            return String::new();
+        }
+        value.extract_string_from(self.get_buffer(), TRIM_MODE_FULL).to_owned()
+    }
+
     pub fn remove_cache(&mut self) {
         Self::remove_value_cache(&mut self.root.borrow_mut());
     }
@@ -686,14 +725,59 @@ impl Brackets {
     fn process_buffer(&mut self) -> Result<(), BracketsError> {
         self.is_processing = true;
         self.reset();
+        self.parse_buffer().map_err(|e| e.locate(self.get_buffer()))?;
+        self.is_processing = false;
+        Ok(())
+    }
+
+    fn parse_buffer(&mut self) -> Result<(), BracketsError> {
         self.spot_bounds()?;
+        self.check_strict()?;
         self.try_link_bounds()?;
         self.start_index = Some(self.get_start_index());
         self.build_values(None, None)?;
-        self.is_processing = false;
         Ok(())
     }
 
+    pub(crate) fn locate(&self, error: BracketsError) -> BracketsError {
+        error.locate(self.get_buffer())
+    }
+
+    // warnings found while parsing, with their position
+    pub fn diagnostics(&self) -> Vec<BracketsError> {
+        self.warnings.iter()
+            .map(|w| self.locate(BracketsError::from_warning(w.warning_code).at(w.idx)))
+            .collect()
+    }
+
+    fn strict_codes(&self) -> Result<Vec<usize>, BracketsError> {
+        let mut codes: Vec<usize> = vec![];
+        for name in self.config.strict.split_whitespace() {
+            if name == STRICT_ALL {
+                codes.extend(self.warnings.iter().map(|w| w.warning_code));
+                continue;
+            }
+            let code = warning_from_name(name)
+                .ok_or_else(|| BracketsError::coded(ErrorCode::InvalidConfig, format!("{}, unknown strict warning '{}'", ErrorCode::InvalidConfig.message(), name)))?;
+            codes.push(code);
+        }
+        Ok(codes)
+    }
+
+    // warnings selected by the strict config are errors
+    fn check_strict(&self) -> Result<(), BracketsError> {
+        let codes = self.strict_codes()?;
+        let mut errors: Vec<BracketsError> = self.warnings.iter()
+            .filter(|w| codes.contains(&w.warning_code))
+            .map(|w| BracketsError::coded(ErrorCode::Warning(w.warning_code), ErrorCode::Warning(w.warning_code).message().to_owned()).at(w.idx))
+            .collect();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let first = errors.remove(0);
+        Err(first.with_diagnostics(errors))
+    }
+
     fn reset(&mut self) {
         self.open_bks.clear();
         self.all_open_bks.clear();
@@ -702,6 +786,7 @@ impl Brackets {
         self.open_bks_hash.clear();
         self.close_bks_hash.clear();
         self.flags.clear();
+        self.warnings.clear();
         self.is_valid = None;
     }
 
@@ -743,6 +828,7 @@ impl Brackets {
         let start_index = self.get_start_index();
         let all_bks = self.get_bounds_mut();
         let mut enm = all_bks.into_iter();
+        let mut failed: Option<BracketsError> = None;
         
         while scan {
             if let Some(bk) = enm.next() {
@@ -750,22 +836,39 @@ impl Brackets {
                 if bk.is_open_first.is_some() { stack_idx.push(bk.idx) }
                 else {
                     if let Some(x) = stack_idx.pop() { bk.linked_idx = x }
-                    else { return Err(BracketsError::error_close(bk)) }
+                    else {
+                        failed = Some(BracketsError::error_close(bk));
+                        scan = false
+                    }
                 }
             }
             else {
                 scan = false
             }
         }
+        if let Some(e) = failed {
+            let others = self.unmatched_bounds().into_iter().filter(|o| o.offset() != e.offset()).collect();
+            return Err(e.with_diagnostics(others));
+        }
         
         let mut map_clos: HashMap<usize, usize> = HashMap::new();
         self.close_bks.iter().enumerate().filter(|(_,c)| c.idx > start_index).for_each(|(u,c)| {
             map_clos.insert(c.linked_idx, c.idx);
             self.close_bks_hash.insert(c.idx, u);
         });
+        // typed values close with a longer token than their type tells at scan time
+        let mut typed_closes: HashMap<usize, BracketType> = HashMap::new();
         self.open_bks.iter_mut().enumerate().filter(|(_,o)| o.idx >= start_index).for_each(|(u,o)| {
             o.linked_idx = *map_clos.get(&o.idx).unwrap();
             self.open_bks_hash.insert(o.idx, u);
+            if let BracketType::Int | BracketType::Date | BracketType::Real = o.typ {
+                typed_closes.insert(o.linked_idx, o.typ.clone());
+            }
+        });
+        self.close_bks.iter_mut().for_each(|c| {
+            if let Some(t) = typed_closes.remove(&c.idx) {
+                c.typ = t;
+            }
         });
 
         self.is_valid = Some(true);
@@ -881,12 +984,21 @@ impl Brackets {
             } else {
                 let (end_index, map) = bk_regex::extract_config(&self.get_buffer()[self.open_bks[0].idx..]);
                 if map.len() == 0 {
-                    return Err(BracketsError::new(INVALID_CONFIG));
+                    return Err(BracketsError::from_code(ErrorCode::InvalidConfig));
                 }
                 
-                for (k, v) in map {
+                // props missing from the document keep the given configuration
+                for (k, v) in map.iter().filter(|(_, v)| !v.is_empty()) {
                     self.config.set_config(k.as_str(), v.as_str());
                 }
+                // so do props written empty, they are reported
+                let buffer = self.get_buffer();
+                let empty: Vec<BracketChunk> = self.open_bks[1..].iter()
+                    .filter(|o| o.idx < end_index)
+                    .filter(|o| buffer[o.idx + 1..].split(CLOSE).next().is_some_and(|v| v.is_empty()))
+                    .map(|o| BracketChunk { warning_code: WARNING_EMPTY_CONFIG, ..o.clone() })
+                    .collect();
+                self.warnings.extend(empty);
                 for o in self.open_bks.iter_mut().filter(|x| x.idx < end_index) {
                     o.typ = BracketType::Config;
                 }
@@ -910,30 +1022,44 @@ impl Brackets {
     fn validate(&self) -> Result<(), BracketsError> {
         if !self.is_valid.unwrap() {
             let mask_found = self
-                .open_bks
+                .warnings
                 .iter()
                 .find(|b| b.warning_code == WARNING_MASK);
             if mask_found.is_none() || self.config.closure_mode == CLOSURE_MODE_RAW {
                 // allow full parse
-                return Err(BracketsError::new(
-                    format!(
-                        "{}{}",
-                        FORMAT_ERROR,
-                        match mask_found {
-                            Some(b) => format!("Potential error was found at {}", b.idx),
-                            _ => String::new(),
-                        }
-                    )
-                    .as_str(),
-                ));
+                let mut unmatched = self.unmatched_bounds();
+                let error = match mask_found {
+                    Some(b) => BracketsError::coded(ErrorCode::Format, format!("{}, potential error", ErrorCode::Format.message())).at(b.idx),
+                    None if !unmatched.is_empty() => unmatched.remove(0),
+                    None => BracketsError::from_code(ErrorCode::Format)
+                };
+                return Err(error.with_diagnostics(unmatched));
             }
         }
         Ok(())
     }
 
+    // closing brackets without an opening one and opening brackets never closed
+    fn unmatched_bounds(&self) -> Vec<BracketsError> {
+        let start_index = self.get_start_index();
+        let mut bounds: Vec<&BracketChunk> = self.open_bks.iter().chain(self.close_bks.iter())
+            .filter(|b| b.idx >= start_index)
+            .collect();
+        bounds.sort();
+        let mut stack: Vec<&BracketChunk> = Vec::new();
+        let mut errors: Vec<BracketsError> = Vec::new();
+        for bk in bounds {
+            if bk.is_open_first.is_some() { stack.push(bk) }
+            else if stack.pop().is_none() { errors.push(BracketsError::error_close(bk)) }
+        }
+        errors.extend(stack.iter().map(|o| BracketsError::from_code(ErrorCode::Unclosed).at(o.idx)));
+        errors.sort_by_key(|e| e.offset());
+        errors
+    }
+
     fn check_buffer(&self) -> Result<(), BracketsError> {
         if self.get_buffer().trim_end().len() == 0 {
-            return Err(BracketsError::new(EMPTY_STRING));
+            return Err(BracketsError::from_code(ErrorCode::EmptyString));
         }
         Ok(())
     }
@@ -945,10 +1071,10 @@ impl Brackets {
         }
         let bf = self.get_buffer();
         if bf.contains(TOKEN_COMMENT_START) {
-            return Err(BracketsError::new(BAD_ENDING_COMMENT));
+            return Err(BracketsError::from_code(ErrorCode::BadEndingComment));
         }
         if bf.contains(TOKEN_COMMENT_END) {
-            return Err(BracketsError::new(BAD_START_COMMENT));
+            return Err(BracketsError::from_code(ErrorCode::BadStartComment));
         }
         
         Ok(())
@@ -968,10 +1094,10 @@ impl Brackets {
                 odd = !odd;
                 let bound = o.unwrap();
                 if odd && bound.1 == TOKEN_COMMENT_START.chars().next().unwrap() {
-                    return Err(BracketsError::new(BAD_ENDING_COMMENT));
+                    return Err(BracketsError::from_code(ErrorCode::BadEndingComment));
                 }
                 if !odd && bound.1 == TOKEN_COMMENT_END.chars().next().unwrap() {
-                    return Err(BracketsError::new(BAD_START_COMMENT));
+                    return Err(BracketsError::from_code(ErrorCode::BadStartComment));
                 }
                 if odd {
                     self.all_close_bks.push(BracketChunk { idx: bound.0, linked_idx: open_idx, typ: BracketType::Comment, is_open_first: None, warning_code: 0 });
@@ -1025,7 +1151,7 @@ impl Brackets {
         if start_is_valid && end_is_valid {
             return Ok(());
         }
-        Err(BracketsError::new(BASE_FORMAT_ERROR))
+        Err(BracketsError::from_code(ErrorCode::BaseFormat))
     }
 
     fn collect_open_bounds(&mut self) {
@@ -1054,6 +1180,11 @@ impl Brackets {
             self.identify_open_bks(&mut real_open_bks, &mut real_close_bks);
         self.identify_close_bks(&mut real_close_bks, &escaped_slices, &mut free_text_ranges);
 
+        // flagged chunks are not kept as brackets, their warnings are
+        self.warnings.extend(self.open_bks.iter().chain(real_close_bks.iter()).filter(|b| b.warning_code > 0).cloned());
+        self.warnings.sort();
+        self.warnings.dedup_by_key(|b| b.idx);
+
         real_open_bks.sort();
         real_close_bks.sort();
         self.open_bks = real_open_bks;
@@ -1180,6 +1311,7 @@ impl Brackets {
                 real_open_bk.push(self.open_bks[index].clone());
             }
             else {
+                self.open_bks[index].warning_code = WARNING_FREE_NAME;
                 free_text_ranges.push(start-1..=end+1);
             }
         }
@@ -1314,14 +1446,128 @@ mod tests_brackets {
         });
     }
 
+    #[test]
+    fn extract_datetime() {
+        let buffer = "x[=date{ 2023-10-10T08:30:00+02:00 }]";
+        let mut value = BracketValue { start: 8, end: 34, btyp: BracketType::Date, ..Default::default() };
+        let d = value.extract_datetime_from(buffer, TRIM_MODE_FULL).unwrap();
+        assert_eq!(d.to_string(), "2023-10-10T08:30:00+02:00");
+        value.btyp = BracketType::Simple;
+        assert!(value.extract_datetime_from(buffer, TRIM_MODE_FULL).is_none());
+    }
+
+    #[test]
+    fn datetime_document() {
+        let text = "[\n    model[\n        event[,name, at<date>,]\n    ]\n    data[\n        events[\n            [\n                name[import]\n                at[=date{2024-01-02T10:00:00Z}]\n            ]\n        ]\n    ]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let found = bk.find("data.events[0].at").unwrap();
+        let BracketSection::Array(ref at) = found[0] else { panic!("at is not an array") };
+        let BracketSection::Str(ref value) = *at.borrow().array[0] else { panic!("at has no value") };
+        let d = value.borrow().extract_datetime_from(bk.get_buffer(), bk.get_trim_mode()).unwrap();
+        assert_eq!(d.to_rfc3339(), "2024-01-02T10:00:00Z");
+        assert!(bk.validate_model().unwrap().is_empty());
+    }
+
+    #[test]
+    fn extract_relative_date() {
+        let buffer = "x[=date{end of last month}]";
+        let value = BracketValue { start: 8, end: 24, btyp: BracketType::Date, ..Default::default() };
+        let d = value.extract_relative_date_from(buffer, TRIM_MODE_FULL, &DayDate::new(2024, 3, 10), &[]);
+        assert_eq!(d, Some(DayDate::new(2024, 2, 29)));
+    }
+
+    #[test]
+    fn typed_value_siblings() {
+        let text = "[\n    a[=int{12}]\n    b[1]\n    d[=date{2024-01-02}]\n    c[x]\n]\n";
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let BracketSection::Array(ref root) = *bk.root.borrow() else { panic!() };
+        let names: Vec<String> = root.borrow().array.iter()
+            .map(|c| match &**c {
+                BracketSection::Array(a) => bk.get_text(&a.borrow().name),
+                _ => String::new()
+            })
+            .collect();
+        assert_eq!(names, vec!["a", "b", "d", "c"]);
+    }
+
     #[test]
     fn invalid_empty_text() {
         let b = Brackets::build_from_string(String::new());
         if let Err(m) = b {
-            assert_eq!(m.message(), EMPTY_STRING);
+            assert_eq!(m.code(), ErrorCode::EmptyString);
+            assert_eq!(m.message(), ErrorCode::EmptyString.message());
         }
     }
 
+    #[test]
+    fn warning_diagnostics() {
+        let text = include_str!("../../data/db.bk");
+        let bk = Brackets::build_from_string(text.to_owned()).unwrap();
+        let codes: Vec<ErrorCode> = bk.diagnostics().iter().map(|d| d.code()).collect();
+        assert_eq!(codes, vec![
+            ErrorCode::Warning(WARNING_FREE_TEXT), ErrorCode::Warning(WARNING_FREE_TEXT), ErrorCode::Warning(WARNING_FREE_NAME),
+            ErrorCode::Warning(WARNING_FREE_TEXT), ErrorCode::Warning(WARNING_FREE_TEXT), ErrorCode::Warning(WARNING_ESCAPED)
+        ]);
+        let escaped = bk.diagnostics().pop().unwrap();
+        assert!(escaped.is_warning());
+        assert_eq!(&text[escaped.offset().unwrap() - 1..=escaped.offset().unwrap()], "\\[");
+        assert_eq!(escaped.location().unwrap().line, 43);
+    }
+
+    #[test]
+    fn strict_config() {
+        let text = include_str!("../../data/db.bk").replace("trimming[start]", "trimming[start]\n    strict[escaped]");
+        let e = Brackets::build_from_string(text.clone()).unwrap_err();
+        assert_eq!(e.code(), ErrorCode::Warning(WARNING_ESCAPED));
+        assert!(!e.is_warning());
+        assert_eq!(e.diagnostics().len(), 1);
+
+        let config = BracketConfig { strict: String::from("free_name free_text"), ..Default::default() };
+        let e = Brackets::build_with_config(include_str!("../../data/db.bk").to_owned(), config).unwrap_err();
+        assert_eq!(e.code(), ErrorCode::Warning(WARNING_FREE_TEXT));
+        assert_eq!(e.diagnostics().len(), 5);
+
+        let config = BracketConfig { strict: String::from("unknown"), ..Default::default() };
+        let e = Brackets::build_with_config(include_str!("../../data/db.bk").to_owned(), config).unwrap_err();
+        assert_eq!(e.code(), ErrorCode::InvalidConfig);
+        assert!(Brackets::build_with_config(String::from("[\n  a[1]\n]\n"), BracketConfig { strict: String::from("all"), ..Default::default() }).is_ok());
+    }
+
+    #[test]
+    fn empty_config_value() {
+        let text = String::from("@[\n    name[doc]\n    trimming[]\n]\n[\n  a[ 1 ]\n]\n");
+        let config = BracketConfig { trimming: String::from("full"), ..Default::default() };
+        let bk = Brackets::build_with_config(text.clone(), config.clone()).unwrap();
+        assert_eq!((bk.config.name.as_str(), bk.config.trimming.as_str()), ("doc", "full"));
+        let warning = bk.diagnostics().pop().unwrap();
+        assert_eq!(warning.code(), ErrorCode::Warning(WARNING_EMPTY_CONFIG));
+        assert_eq!(&text[warning.offset().unwrap() - 8..=warning.offset().unwrap() + 1], "trimming[]");
+
+        let strict = BracketConfig { strict: String::from("empty_config"), ..config };
+        let e = Brackets::build_with_config(text, strict).unwrap_err();
+        assert_eq!(e.code(), ErrorCode::Warning(WARNING_EMPTY_CONFIG));
+
+        // a value made of spaces is not empty, it is applied
+        let text = String::from("@[\n    name[ ]\n]\n[\n  a[ 1 ]\n]\n");
+        let bk = Brackets::build_with_config(text, BracketConfig { name: String::from("given"), ..Default::default() }).unwrap();
+        assert_eq!(bk.config.name.as_str(), " ");
+        assert!(bk.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn unmatched_diagnostics() {
+        let e = Brackets::build_from_string(String::from("[\n  a[1]]\n  b[2]]\n  c[[3]\n]\n")).unwrap_err();
+        assert_eq!(e.code(), ErrorCode::BadEnding);
+        let location = e.location().unwrap();
+        assert_eq!((location.line, location.column), (3, 7));
+        assert_eq!(location.snippet, "3 |   b[2]]\n  |       ^");
+
+        let e = Brackets::build_from_string(String::from("[\n  a[1]\n  b[2\n  c[3\n]\n")).unwrap_err();
+        let diagnostics: Vec<(ErrorCode, Option<usize>)> = e.diagnostics().iter().map(|d| (d.code(), d.offset())).collect();
+        assert_eq!(diagnostics, vec![(ErrorCode::Unclosed, Some(0)), (ErrorCode::Unclosed, Some(12))]);
+        assert_eq!(e.to_string().lines().count(), 6);
+    }
+
     #[test]
     fn invalid_text() {
         let b = Brackets::build_from_string(String::from("(dddd) //lo"));
@@ -1437,3 +1683,9 @@ pub mod bk_regex;
 pub mod bk_query;
 pub mod bk_json;
 pub mod bk_file;
+pub mod bk_mapping;
+pub mod bk_schema;
+pub mod bk_edit;
+pub mod bk_cst;
+pub mod bk_pretty;
+pub mod bk_stream;